@@ -0,0 +1,227 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+// Postgres truncates identifiers longer than NAMEDATALEN - 1 bytes.
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+
+/// Implements `sqlx_helpers::Table` for a struct with named fields and adds typed
+/// `insert`, `update`, `select_by_key` and `delete` methods.
+///
+/// ```ignore
+/// #[derive(Table)]
+/// #[table(name = "book", key = "isbn")]
+/// struct Book {
+///     title: String,
+///     author: String,
+///     isbn: String,
+/// }
+/// ```
+///
+/// Each field is a column of the same name, and `key` must name one of the fields.
+/// Field types convert into `SqlValue` with `From` and back with `FromSqlValue`.
+#[proc_macro_derive(Table, attributes(table))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let (table_name, key_name) = table_attribute(&input)?;
+    validate_identifier(&table_name, true)?;
+    validate_identifier(&key_name, false)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "Table can only be derived for structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "Table can only be derived for structs")),
+    };
+
+    let idents: Vec<&syn::Ident> = fields.iter().filter_map(|field| field.ident.as_ref()).collect();
+    let columns: Vec<String> = idents.iter().map(|ident| ident.to_string().trim_start_matches("r#").to_owned()).collect();
+    for (ident, column) in idents.iter().zip(&columns) {
+        validate_identifier(&LitStr::new(column, ident.span()), false)?;
+    }
+
+    let key = match columns.iter().position(|column| *column == key_name.value()) {
+        Some(index) => idents[index],
+        None => return Err(syn::Error::new(key_name.span(), format!("no field named `{}`", key_name.value()))),
+    };
+    if columns.len() < 2 {
+        return Err(syn::Error::new_spanned(&input.ident, "Table needs at least one column besides the key"));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::sqlx_helpers::Table for #name #type_generics #where_clause {
+            const NAME: &'static str = #table_name;
+            const KEY: &'static str = #key_name;
+            const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+
+            fn values(&self) -> ::std::vec::Vec<::sqlx_helpers::SqlValue> {
+                ::std::vec::Vec::from([#(::sqlx_helpers::SqlValue::from(::std::clone::Clone::clone(&self.#idents))),*])
+            }
+
+            fn key(&self) -> ::sqlx_helpers::SqlValue {
+                ::sqlx_helpers::SqlValue::from(::std::clone::Clone::clone(&self.#key))
+            }
+
+            fn from_row(columns: &[::std::string::String], mut row: ::std::vec::Vec<::sqlx_helpers::SqlValue>) -> ::std::result::Result<Self, ::sqlx_helpers::HelperError> {
+                ::std::result::Result::Ok(#name {
+                    #(#idents: ::sqlx_helpers::__private::take_column(columns, &mut row, #columns)?,)*
+                })
+            }
+        }
+
+        impl #impl_generics #name #type_generics #where_clause {
+            pub async fn insert<'c, E: ::sqlx_helpers::HelperExecutor<'c>>(&self, executor: E) -> ::std::result::Result<u64, ::sqlx_helpers::HelperError>
+            {
+                ::sqlx_helpers::insert_record(self, executor).await
+            }
+
+            pub async fn update<'c, E: ::sqlx_helpers::HelperExecutor<'c>>(&self, executor: E) -> ::std::result::Result<u64, ::sqlx_helpers::HelperError>
+            {
+                ::sqlx_helpers::update_record(self, executor).await
+            }
+
+            pub async fn select_by_key<'c, E: ::sqlx_helpers::HelperExecutor<'c>>(key: impl ::std::convert::Into<::sqlx_helpers::SqlValue>, executor: E) -> ::std::result::Result<::std::option::Option<Self>, ::sqlx_helpers::HelperError>
+            {
+                ::sqlx_helpers::select_record(key.into(), executor).await
+            }
+
+            pub async fn delete<'c, E: ::sqlx_helpers::HelperExecutor<'c>>(&self, executor: E) -> ::std::result::Result<u64, ::sqlx_helpers::HelperError>
+            {
+                ::sqlx_helpers::delete_record::<Self, E>(::sqlx_helpers::Table::key(self), executor).await
+            }
+        }
+    })
+}
+
+// Reads `#[table(name = "...", key = "...")]`. Both are required.
+fn table_attribute(input: &DeriveInput) -> syn::Result<(LitStr, LitStr)> {
+    let mut name = None;
+    let mut key = None;
+
+    for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("table")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("key") {
+                key = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `key`"))
+            }
+        })?;
+    }
+
+    let missing = |what: &str| syn::Error::new(Span::call_site(), format!("missing #[table({what} = \"...\")] attribute"));
+    Ok((name.ok_or_else(|| missing("name"))?, key.ok_or_else(|| missing("key"))?))
+}
+
+// The rules of `sqlx_helpers::identifiers`, checked here so a bad name fails the build
+// instead of every statement. `qualified` allows one `schema.` prefix, as table names do.
+fn validate_identifier(name: &LitStr, qualified: bool) -> syn::Result<()> {
+    let value = name.value();
+    let parts: Vec<&str> = value.split('.').collect();
+    if parts.len() > if qualified { 2 } else { 1 } {
+        return Err(syn::Error::new(name.span(), format!("identifier \"{value}\" has too many dot-separated parts")));
+    }
+
+    for part in parts {
+        let mut chars = part.chars();
+        let first = match chars.next() {
+            Some(c) => c,
+            None => return Err(syn::Error::new(name.span(), "identifier is empty")),
+        };
+
+        if part.len() > MAX_IDENTIFIER_LENGTH {
+            return Err(syn::Error::new(name.span(), format!("identifier \"{value}\" is longer than {MAX_IDENTIFIER_LENGTH} bytes")));
+        }
+
+        if !(first.is_alphabetic() || first == '_') {
+            return Err(syn::Error::new(name.span(), format!("identifier \"{value}\" contains invalid character {first:?}")));
+        }
+
+        if let Some(c) = chars.find(|&c| !(c.is_alphanumeric() || c == '_' || c == '$')) {
+            return Err(syn::Error::new(name.span(), format!("identifier \"{value}\" contains invalid character {c:?}")));
+        }
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_error(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_valid_table() {
+        let input = syn::parse_quote! {
+            #[table(name = "library.book", key = "isbn")]
+            struct Book {
+                title: String,
+                isbn: String,
+            }
+        };
+        assert!(expand(input).is_ok());
+    }
+
+    #[test]
+    fn test_rejected_identifiers() {
+        let error = expand_error(syn::parse_quote! {
+            #[table(name = "book; DROP TABLE book", key = "isbn")]
+            struct Book { title: String, isbn: String }
+        });
+        assert_eq!(error, "identifier \"book; DROP TABLE book\" contains invalid character ';'");
+
+        let error = expand_error(syn::parse_quote! {
+            #[table(name = "a.b.c", key = "isbn")]
+            struct Book { title: String, isbn: String }
+        });
+        assert_eq!(error, "identifier \"a.b.c\" has too many dot-separated parts");
+
+        let error = expand_error(syn::parse_quote! {
+            #[table(name = "book", key = "")]
+            struct Book { title: String, isbn: String }
+        });
+        assert_eq!(error, "identifier is empty");
+
+        let error = expand_error(syn::parse_quote! {
+            #[table(name = "book", key = "book.isbn")]
+            struct Book { title: String, isbn: String }
+        });
+        assert_eq!(error, "identifier \"book.isbn\" has too many dot-separated parts");
+
+        let long = "a".repeat(64);
+        let error = expand_error(syn::parse_quote! {
+            #[table(name = #long, key = "isbn")]
+            struct Book { title: String, isbn: String }
+        });
+        assert_eq!(error, format!("identifier \"{long}\" is longer than 63 bytes"));
+
+        let error = expand_error(syn::parse_quote! {
+            #[table(name = "1book", key = "isbn")]
+            struct Book { title: String, isbn: String }
+        });
+        assert_eq!(error, "identifier \"1book\" contains invalid character '1'");
+    }
+
+}
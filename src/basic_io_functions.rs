@@ -1,4 +1,4 @@
-use std::{path::Path, fs::read_to_string, collections::HashMap};
+use std::{path::Path, fs::read_to_string, collections::HashMap, io};
 
 pub fn split_string(s: String, sep: char) -> Vec<String> {
     let mut output = Vec::new();
@@ -60,10 +60,14 @@ pub fn read_to_hashmap(path: &Path, sep: char, pk: usize) -> (Vec<String>, HashM
 
 
 pub fn read_to_vec(path: &Path, sep: char) -> (Vec<String>, Vec<Vec<String>>) {
-    let s = match read_to_string(path) {
+    match try_read_to_vec(path, sep) {
         Err(why) => panic!("Could not load file {} because {}", path.display(), why),
         Ok(content) => content,
-    };
+    }
+}
+
+pub fn try_read_to_vec(path: &Path, sep: char) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let s = read_to_string(path)?;
 
     let lines = split_string(s, '\n');
 
@@ -73,8 +77,12 @@ pub fn read_to_vec(path: &Path, sep: char) -> (Vec<String>, Vec<Vec<String>>) {
         output.push(temp);
     }
 
+    if output.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "file has no header line"));
+    }
+
     let header = output.remove(0);
-    (header, output)
+    Ok((header, output))
 }
 
 pub fn vec_string_to_str(vec: &Vec<String>) -> Vec<&str> {
@@ -0,0 +1,292 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    identifiers::{quote_identifier, IdentifierError},
+    Dialect, SqlValue,
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Less,
+    LessOrEq,
+    Greater,
+    GreaterOrEq,
+}
+
+impl Comparison {
+    fn operator(&self) -> &'static str {
+        match self {
+            Comparison::Eq => " = ",
+            Comparison::NotEq => " <> ",
+            Comparison::Less => " < ",
+            Comparison::LessOrEq => " <= ",
+            Comparison::Greater => " > ",
+            Comparison::GreaterOrEq => " >= ",
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnfilteredDelete(pub String);
+
+impl fmt::Display for UnfilteredDelete {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "refusing to delete every row of \"{}\"; use delete_all to do that on purpose", self.0)
+    }
+}
+
+impl Error for UnfilteredDelete {}
+
+
+/// A WHERE clause for `update`, `select` and `delete`.
+/// Every operand is bound as a parameter, and nested `And`/`Or` groups are parenthesized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare(String, Comparison, SqlValue),
+    In(String, Vec<SqlValue>),
+    Like(String, SqlValue),
+    ILike(String, SqlValue),
+    Between(String, SqlValue, SqlValue),
+    IsNull(String),
+    IsNotNull(String),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn eq(column: &str, value: impl Into<SqlValue>) -> Self {
+        Condition::Compare(column.to_owned(), Comparison::Eq, value.into())
+    }
+
+    pub fn ne(column: &str, value: impl Into<SqlValue>) -> Self {
+        Condition::Compare(column.to_owned(), Comparison::NotEq, value.into())
+    }
+
+    pub fn lt(column: &str, value: impl Into<SqlValue>) -> Self {
+        Condition::Compare(column.to_owned(), Comparison::Less, value.into())
+    }
+
+    pub fn le(column: &str, value: impl Into<SqlValue>) -> Self {
+        Condition::Compare(column.to_owned(), Comparison::LessOrEq, value.into())
+    }
+
+    pub fn gt(column: &str, value: impl Into<SqlValue>) -> Self {
+        Condition::Compare(column.to_owned(), Comparison::Greater, value.into())
+    }
+
+    pub fn ge(column: &str, value: impl Into<SqlValue>) -> Self {
+        Condition::Compare(column.to_owned(), Comparison::GreaterOrEq, value.into())
+    }
+
+    pub fn in_list<V: Into<SqlValue>>(column: &str, values: Vec<V>) -> Self {
+        Condition::In(column.to_owned(), values.into_iter().map(Into::into).collect())
+    }
+
+    pub fn like(column: &str, pattern: impl Into<SqlValue>) -> Self {
+        Condition::Like(column.to_owned(), pattern.into())
+    }
+
+    pub fn ilike(column: &str, pattern: impl Into<SqlValue>) -> Self {
+        Condition::ILike(column.to_owned(), pattern.into())
+    }
+
+    pub fn between(column: &str, low: impl Into<SqlValue>, high: impl Into<SqlValue>) -> Self {
+        Condition::Between(column.to_owned(), low.into(), high.into())
+    }
+
+    pub fn is_null(column: &str) -> Self {
+        Condition::IsNull(column.to_owned())
+    }
+
+    pub fn is_not_null(column: &str) -> Self {
+        Condition::IsNotNull(column.to_owned())
+    }
+
+    pub fn all(conditions: Vec<Condition>) -> Self {
+        Condition::And(conditions)
+    }
+
+    pub fn any(conditions: Vec<Condition>) -> Self {
+        Condition::Or(conditions)
+    }
+
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::And(mut conditions) => {
+                conditions.push(other);
+                Condition::And(conditions)
+            },
+            condition => Condition::And(Vec::from([condition, other])),
+        }
+    }
+
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Or(mut conditions) => {
+                conditions.push(other);
+                Condition::Or(conditions)
+            },
+            condition => Condition::Or(Vec::from([condition, other])),
+        }
+    }
+
+    // True for conditions like `Condition::all(Vec::new())` that match every row no
+    // matter what is in the table.
+    pub(crate) fn is_unfiltered(&self) -> bool {
+        match self {
+            Condition::And(conditions) => conditions.iter().all(Condition::is_unfiltered),
+            Condition::Or(conditions) => conditions.iter().any(Condition::is_unfiltered),
+            _ => false,
+        }
+    }
+
+    // Placeholders continue from whatever is already in `params`, so the clause
+    // can follow the SET list of an UPDATE.
+    pub(crate) fn push_sql<D: Dialect + ?Sized>(self, dialect: &D, query: &mut String, params: &mut Vec<SqlValue>) -> Result<(), IdentifierError> {
+        match self {
+            Condition::Compare(column, comparison, value) => {
+                dialect.push_identifier(query, &column)?;
+                query.push_str(comparison.operator());
+                push_param(dialect, query, params, value);
+            },
+            Condition::In(column, values) => {
+                if values.is_empty() {
+                    // "x IN ()" is a syntax error, and an empty list never matches.
+                    quote_identifier(&column)?;
+                    query.push_str("FALSE");
+                    return Ok(());
+                }
+                dialect.push_identifier(query, &column)?;
+                query.push_str(" IN (");
+                for value in values {
+                    push_param(dialect, query, params, value);
+                    query.push(',');
+                }
+                query.pop();
+                query.push(')');
+            },
+            Condition::Like(column, pattern) => {
+                dialect.push_identifier(query, &column)?;
+                query.push_str(" LIKE ");
+                push_param(dialect, query, params, pattern);
+            },
+            Condition::ILike(column, pattern) => {
+                params.push(pattern);
+                dialect.push_ilike(query, &column, params.len())?;
+            },
+            Condition::Between(column, low, high) => {
+                dialect.push_identifier(query, &column)?;
+                query.push_str(" BETWEEN ");
+                push_param(dialect, query, params, low);
+                query.push_str(" AND ");
+                push_param(dialect, query, params, high);
+            },
+            Condition::IsNull(column) => {
+                dialect.push_identifier(query, &column)?;
+                query.push_str(" IS NULL");
+            },
+            Condition::IsNotNull(column) => {
+                dialect.push_identifier(query, &column)?;
+                query.push_str(" IS NOT NULL");
+            },
+            Condition::And(conditions) => push_group(dialect, query, params, conditions, " AND ", "TRUE")?,
+            Condition::Or(conditions) => push_group(dialect, query, params, conditions, " OR ", "FALSE")?,
+        }
+
+        Ok(())
+    }
+}
+
+
+fn push_param<D: Dialect + ?Sized>(dialect: &D, query: &mut String, params: &mut Vec<SqlValue>, value: SqlValue) {
+    params.push(value);
+    dialect.push_placeholder(query, params.len());
+}
+
+fn push_group<D: Dialect + ?Sized>(dialect: &D, query: &mut String, params: &mut Vec<SqlValue>, conditions: Vec<Condition>, separator: &str, empty: &str) -> Result<(), IdentifierError> {
+    if conditions.is_empty() {
+        query.push_str(empty);
+        return Ok(());
+    }
+
+    query.push('(');
+    for condition in conditions {
+        condition.push_sql(dialect, query, params)?;
+        query.push_str(separator);
+    }
+    query.truncate(query.len() - separator.len());
+    query.push(')');
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PostgresDialect;
+
+    fn render(condition: Condition) -> (String, Vec<SqlValue>) {
+        let mut query = String::new();
+        let mut params = Vec::new();
+        condition.push_sql(&PostgresDialect, &mut query, &mut params).unwrap();
+        (query, params)
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(render(Condition::eq("isbn", "1")), ("\"isbn\" = $1".to_owned(), Vec::from([SqlValue::from("1")])));
+        assert_eq!(render(Condition::ne("isbn", "1")).0, "\"isbn\" <> $1");
+        assert_eq!(render(Condition::lt("pages", 100)).0, "\"pages\" < $1");
+        assert_eq!(render(Condition::gt("pages", 100)).0, "\"pages\" > $1");
+        assert_eq!(render(Condition::is_null("author")).0, "\"author\" IS NULL");
+        assert_eq!(render(Condition::is_not_null("author")).0, "\"author\" IS NOT NULL");
+    }
+
+    #[test]
+    fn test_in_like_between() {
+        let (query, params) = render(Condition::in_list("isbn", Vec::from(["0", "1"])));
+        assert_eq!(query, "\"isbn\" IN ($1,$2)");
+        assert_eq!(params.len(), 2);
+
+        assert_eq!(render(Condition::in_list::<&str>("isbn", Vec::new())).0, "FALSE");
+        assert_eq!(render(Condition::like("title", "The %")).0, "\"title\" LIKE $1");
+        assert_eq!(render(Condition::ilike("title", "the %")).0, "\"title\" ILIKE $1");
+        assert_eq!(render(Condition::between("pages", 100, 200)).0, "\"pages\" BETWEEN $1 AND $2");
+    }
+
+    #[test]
+    fn test_nesting() {
+        let condition = Condition::eq("author", "JRR Tolkien")
+            .and(Condition::any(Vec::from([Condition::like("title", "The %"), Condition::is_null("isbn")])))
+            .and(Condition::ne("isbn", "1"));
+
+        let (query, params) = render(condition);
+        assert_eq!(query, "(\"author\" = $1 AND (\"title\" LIKE $2 OR \"isbn\" IS NULL) AND \"isbn\" <> $3)");
+        assert_eq!(params, Vec::from([SqlValue::from("JRR Tolkien"), SqlValue::from("The %"), SqlValue::from("1")]));
+
+        assert_eq!(render(Condition::all(Vec::new())).0, "TRUE");
+        assert_eq!(render(Condition::any(Vec::new())).0, "FALSE");
+    }
+
+    #[test]
+    fn test_is_unfiltered() {
+        assert!(Condition::all(Vec::new()).is_unfiltered());
+        assert!(Condition::any(Vec::from([Condition::eq("isbn", "1"), Condition::all(Vec::new())])).is_unfiltered());
+        assert!(!Condition::any(Vec::new()).is_unfiltered());
+        assert!(!Condition::all(Vec::from([Condition::is_null("isbn")])).is_unfiltered());
+    }
+
+    #[test]
+    fn test_invalid_column() {
+        let mut query = String::new();
+        let mut params = Vec::new();
+        let result = Condition::eq("isbn OR 1=1", "1").push_sql(&PostgresDialect, &mut query, &mut params);
+        assert!(result.is_err());
+    }
+
+}
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, path::Path};
+
+use futures::StreamExt;
+use sqlx::{Acquire, Postgres};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    basic_io_functions::try_read_to_vec,
+    identifiers::{push_identifier, push_qualified_identifier, IdentifierError},
+    HelperError,
+};
+
+// Rows are buffered and handed to Postgres in chunks of roughly this size.
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+// The CSV quote character of exports, removed from the output. A control character is
+// never part of a multi-byte UTF-8 sequence, so dropping the byte cannot split a character.
+const COPY_QUOTE: u8 = 0x01;
+
+
+/// How delimited files are read by `copy_in` and written by `export_table`.
+/// `separator` is the same character passed to `read_to_vec`, and any cell equal to
+/// `null_token` stands for NULL. `header` only affects exports; `copy_in` always
+/// takes the header from the first line, as `read_to_vec` does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyOptions {
+    pub separator: char,
+    pub null_token: Option<String>,
+    pub header: bool,
+    pub renames: HashMap<String, String>,
+}
+
+impl CopyOptions {
+    pub fn new(separator: char) -> Self {
+        CopyOptions { separator, null_token: None, header: true, renames: HashMap::new() }
+    }
+
+    pub fn without_header(mut self) -> Self {
+        self.header = false;
+        self
+    }
+
+    pub fn null_token(mut self, token: &str) -> Self {
+        self.null_token = Some(token.to_owned());
+        self
+    }
+
+    /// Loads the file column `header` into the table column `column`.
+    pub fn rename(mut self, header: &str, column: &str) -> Self {
+        self.renames.insert(header.to_owned(), column.to_owned());
+        self
+    }
+
+
+    fn column_for<'a>(&'a self, header: &'a str) -> &'a str {
+        match self.renames.get(header) {
+            Some(column) => column,
+            None => header,
+        }
+    }
+}
+
+
+pub fn format_copy_in_statement(table_name: &str, header: &[String], options: &CopyOptions) -> Result<String, IdentifierError> {
+    let mut query = String::from("COPY ");
+    push_qualified_identifier(&mut query, table_name)?;
+
+    query.push_str(" (");
+    for name in header {
+        push_identifier(&mut query, options.column_for(name))?;
+        query.push(',');
+    }
+    query.pop();
+    query.push_str(") FROM STDIN");
+
+    Ok(query)
+}
+
+/// Loads rows as returned by `read_to_vec` with COPY FROM STDIN and returns the number of rows copied.
+/// COPY is a connection-level protocol, so this takes a pool, connection or transaction to acquire one from.
+/// Missing trailing cells, which `split_string` drops when they are empty, are loaded as empty strings.
+pub async fn copy_in<'c, A: Acquire<'c, Database = Postgres>>(table_name: &str, header: &[String], rows: Vec<Vec<String>>, options: &CopyOptions, connection: A) -> Result<u64, HelperError> {
+    let statement = format_copy_in_statement(table_name, header, options)?;
+    let mut conn = connection.acquire().await?;
+    let mut copy = conn.copy_in_raw(&statement).await?;
+
+    let mut buffer = String::new();
+    for row in rows {
+        push_copy_row(&mut buffer, header.len(), row, options);
+        if buffer.len() >= COPY_CHUNK_SIZE {
+            copy.send(buffer.as_bytes()).await?;
+            buffer.clear();
+        }
+    }
+    if !buffer.is_empty() {
+        copy.send(buffer.as_bytes()).await?;
+    }
+
+    Ok(copy.finish().await?)
+}
+
+pub async fn copy_in_file<'c, A: Acquire<'c, Database = Postgres>>(table_name: &str, path: &Path, options: &CopyOptions, connection: A) -> Result<u64, HelperError> {
+    let (header, rows) = try_read_to_vec(path, options.separator)?;
+
+    copy_in(table_name, &header, rows, options, connection).await
+}
+
+
+pub fn format_copy_out_statement(table_name: &str, columns: &[String], options: &CopyOptions) -> Result<String, IdentifierError> {
+    let mut query = String::from("COPY ");
+    push_qualified_identifier(&mut query, table_name)?;
+
+    if !columns.is_empty() {
+        query.push_str(" (");
+        for column in columns {
+            push_identifier(&mut query, column)?;
+            query.push(',');
+        }
+        query.pop();
+        query.push(')');
+    }
+
+    query.push_str(" TO STDOUT");
+    push_copy_out_options(&mut query, options);
+
+    Ok(query)
+}
+
+/// Streams a whole table, or just `columns` of it, to `writer` in the format `read_to_vec` reads.
+/// Cells are written as they are, never quoted or escaped, so a `"` in a title reads back
+/// unchanged. `read_to_vec` has no quoting either, so a cell containing the separator or
+/// a line break cannot be read back correctly. Returns the number of bytes written.
+pub async fn export_table<'c, W: AsyncWrite + Unpin, A: Acquire<'c, Database = Postgres>>(table_name: &str, columns: &[String], options: &CopyOptions, writer: &mut W, connection: A) -> Result<u64, HelperError> {
+    let statement = format_copy_out_statement(table_name, columns, options)?;
+
+    copy_out(&statement, writer, connection).await
+}
+
+/// Like `export_table`, but for the result of a SELECT. COPY cannot take bind parameters,
+/// so `query` must not contain untrusted input.
+pub async fn export_query<'c, W: AsyncWrite + Unpin, A: Acquire<'c, Database = Postgres>>(query: &str, options: &CopyOptions, writer: &mut W, connection: A) -> Result<u64, HelperError> {
+    let mut statement = String::from("COPY (");
+    statement.push_str(query);
+    statement.push_str(") TO STDOUT");
+    push_copy_out_options(&mut statement, options);
+
+    copy_out(&statement, writer, connection).await
+}
+
+pub async fn export_table_to_file<'c, A: Acquire<'c, Database = Postgres>>(table_name: &str, columns: &[String], path: &Path, options: &CopyOptions, connection: A) -> Result<u64, HelperError> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let written = export_table(table_name, columns, options, &mut file, connection).await?;
+    file.flush().await?;
+
+    Ok(written)
+}
+
+
+async fn copy_out<'c, W: AsyncWrite + Unpin, A: Acquire<'c, Database = Postgres>>(statement: &str, writer: &mut W, connection: A) -> Result<u64, HelperError> {
+    let mut conn = connection.acquire().await?;
+    let mut chunks = conn.copy_out_raw(statement).await?;
+
+    let mut written = 0;
+    while let Some(chunk) = chunks.next().await {
+        let chunk: Vec<u8> = chunk?.into_iter().filter(|&byte| byte != COPY_QUOTE).collect();
+        writer.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+
+    Ok(written)
+}
+
+// CSV format is used for its plain NULL and DELIMITER handling, with a quote character
+// that does not occur in text so that `copy_out` can drop every quote Postgres writes.
+fn push_copy_out_options(query: &mut String, options: &CopyOptions) {
+    query.push_str(" WITH (FORMAT csv, QUOTE E'\\x01', DELIMITER ");
+    push_string_literal(query, &options.separator.to_string());
+    if options.header {
+        query.push_str(", HEADER true");
+    }
+    if let Some(token) = &options.null_token {
+        query.push_str(", NULL ");
+        push_string_literal(query, token);
+    }
+    query.push(')');
+}
+
+// COPY options are not bindable, so they are written as standard string literals.
+fn push_string_literal(query: &mut String, value: &str) {
+    query.push('\'');
+    query.push_str(&value.replace('\'', "''"));
+    query.push('\'');
+}
+
+// Writes one line of COPY text format: tab separated, backslash escaped, \N for NULL.
+fn push_copy_row(buffer: &mut String, columns: usize, mut row: Vec<String>, options: &CopyOptions) {
+    row.resize(columns.max(row.len()), String::new());
+
+    for cell in row {
+        if options.null_token.as_deref() == Some(cell.as_str()) {
+            buffer.push_str("\\N");
+        } else {
+            for c in cell.chars() {
+                match c {
+                    '\\' => buffer.push_str("\\\\"),
+                    '\t' => buffer.push_str("\\t"),
+                    '\n' => buffer.push_str("\\n"),
+                    '\r' => buffer.push_str("\\r"),
+                    c => buffer.push(c),
+                }
+            }
+        }
+        buffer.push('\t');
+    }
+    buffer.pop();
+    buffer.push('\n');
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_in_statement() {
+        let header = Vec::from(["title".to_owned(), "author".to_owned(), "isbn_13".to_owned()]);
+        let options = CopyOptions::new(';').rename("isbn_13", "isbn");
+
+        let statement = format_copy_in_statement("library.book", &header, &options).unwrap();
+        assert_eq!(statement, "COPY \"library\".\"book\" (\"title\",\"author\",\"isbn\") FROM STDIN");
+    }
+
+    #[test]
+    fn test_copy_out_statement() {
+        let columns = Vec::from(["title".to_owned(), "isbn".to_owned()]);
+        let options = CopyOptions::new(';').null_token("it's null");
+
+        let statement = format_copy_out_statement("book", &columns, &options).unwrap();
+        assert_eq!(statement, "COPY \"book\" (\"title\",\"isbn\") TO STDOUT WITH (FORMAT csv, QUOTE E'\\x01', DELIMITER ';', HEADER true, NULL 'it''s null')");
+
+        let statement = format_copy_out_statement("book", &[], &CopyOptions::new('\t').without_header()).unwrap();
+        assert_eq!(statement, "COPY \"book\" TO STDOUT WITH (FORMAT csv, QUOTE E'\\x01', DELIMITER '\t')");
+    }
+
+    #[test]
+    fn test_push_copy_row() {
+        let options = CopyOptions::new(';').null_token("NULL");
+
+        let mut buffer = String::new();
+        push_copy_row(&mut buffer, 3, Vec::from(["C:\\books".to_owned(), "NULL".to_owned()]), &options);
+        push_copy_row(&mut buffer, 3, Vec::from(["a\tb".to_owned(), "".to_owned(), "2".to_owned()]), &options);
+
+        assert_eq!(buffer, "C:\\\\books\t\\N\t\na\\tb\t\t2\n");
+    }
+
+}
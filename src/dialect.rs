@@ -0,0 +1,291 @@
+use crate::{
+    identifiers::{push_identifier_quoted, push_qualified_identifier_quoted, IdentifierError},
+    upsert::{ConflictAction, ConflictTarget},
+    NullsOrder, OnConflict, OrderBy, SortDirection,
+};
+
+
+/// The parts of the SQL the builders write that differ between databases.
+/// Pass one of `PostgresDialect`, `SqliteDialect` or `MySqlDialect` to the
+/// `*_with_dialect` builders; the builders without the suffix use Postgres.
+pub trait Dialect {
+    /// Appends the placeholder for the `position`th bound parameter, counting from 1.
+    fn push_placeholder(&self, query: &mut String, position: usize);
+
+    /// The character identifiers are wrapped in.
+    fn identifier_quote(&self) -> char {
+        '"'
+    }
+
+    /// Whether INSERT and UPDATE can end in a RETURNING clause.
+    fn supports_returning(&self) -> bool;
+
+    /// Appends the clause that turns an INSERT of `columns` into an upsert.
+    fn push_on_conflict(&self, query: &mut String, on_conflict: &OnConflict, _columns: &[String]) -> Result<(), IdentifierError> {
+        on_conflict.push_sql(self, query)
+    }
+
+    /// Appends a case-insensitive LIKE of `column` against the `position`th bound parameter.
+    fn push_ilike(&self, query: &mut String, column: &str, position: usize) -> Result<(), IdentifierError> {
+        self.push_identifier(query, column)?;
+        query.push_str(" ILIKE ");
+        self.push_placeholder(query, position);
+        Ok(())
+    }
+
+    /// Appends one entry of an ORDER BY list.
+    fn push_order_by(&self, query: &mut String, order: &OrderBy) -> Result<(), IdentifierError> {
+        self.push_identifier(query, &order.column)?;
+        push_direction(query, order.direction);
+        match order.nulls {
+            Some(NullsOrder::First) => query.push_str(" NULLS FIRST"),
+            Some(NullsOrder::Last) => query.push_str(" NULLS LAST"),
+            None => (),
+        }
+        Ok(())
+    }
+
+    fn push_identifier(&self, query: &mut String, name: &str) -> Result<(), IdentifierError> {
+        push_identifier_quoted(query, name, self.identifier_quote())
+    }
+
+    fn push_qualified_identifier(&self, query: &mut String, name: &str) -> Result<(), IdentifierError> {
+        push_qualified_identifier_quoted(query, name, self.identifier_quote())
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PostgresDialect;
+
+impl Dialect for PostgresDialect {
+    fn push_placeholder(&self, query: &mut String, position: usize) {
+        query.push('$');
+        query.push_str(&position.to_string());
+    }
+
+    fn supports_returning(&self) -> bool {
+        true
+    }
+}
+
+
+/// SQLite 3.35 or newer, which added RETURNING.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SqliteDialect;
+
+impl Dialect for SqliteDialect {
+    fn push_placeholder(&self, query: &mut String, position: usize) {
+        query.push('?');
+        query.push_str(&position.to_string());
+    }
+
+    fn supports_returning(&self) -> bool {
+        true
+    }
+
+    // SQLite has no ILIKE, but its LIKE already ignores case, if only for ASCII letters.
+    fn push_ilike(&self, query: &mut String, column: &str, position: usize) -> Result<(), IdentifierError> {
+        self.push_identifier(query, column)?;
+        query.push_str(" LIKE ");
+        self.push_placeholder(query, position);
+        Ok(())
+    }
+
+    // SQLite cannot name a constraint as the conflict target, but the last
+    // ON CONFLICT clause may leave the target out and match any unique index.
+    fn push_on_conflict(&self, query: &mut String, on_conflict: &OnConflict, _columns: &[String]) -> Result<(), IdentifierError> {
+        query.push_str(" ON CONFLICT");
+        if let ConflictTarget::Columns(_) = on_conflict.target {
+            on_conflict.push_target(self, query)?;
+        }
+        on_conflict.push_action(self, query)
+    }
+}
+
+
+/// MySQL binds `?` placeholders strictly in order, which is the order the builders
+/// push parameters in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MySqlDialect;
+
+impl Dialect for MySqlDialect {
+    fn push_placeholder(&self, query: &mut String, _position: usize) {
+        query.push('?');
+    }
+
+    fn identifier_quote(&self) -> char {
+        '`'
+    }
+
+    fn supports_returning(&self) -> bool {
+        false
+    }
+
+    // Whether LIKE ignores case depends on the column's collation, so lower both sides.
+    fn push_ilike(&self, query: &mut String, column: &str, position: usize) -> Result<(), IdentifierError> {
+        query.push_str("LOWER(");
+        self.push_identifier(query, column)?;
+        query.push_str(") LIKE LOWER(");
+        self.push_placeholder(query, position);
+        query.push(')');
+        Ok(())
+    }
+
+    // There is no NULLS FIRST/LAST, but false sorts before true, so a leading
+    // `column IS NULL` key puts NULLs last and `column IS NOT NULL` puts them first.
+    fn push_order_by(&self, query: &mut String, order: &OrderBy) -> Result<(), IdentifierError> {
+        match order.nulls {
+            Some(NullsOrder::First) => {
+                self.push_identifier(query, &order.column)?;
+                query.push_str(" IS NOT NULL,");
+            },
+            Some(NullsOrder::Last) => {
+                self.push_identifier(query, &order.column)?;
+                query.push_str(" IS NULL,");
+            },
+            None => (),
+        }
+        self.push_identifier(query, &order.column)?;
+        push_direction(query, order.direction);
+        Ok(())
+    }
+
+    // ON DUPLICATE KEY UPDATE fires on any unique key, so the target is not written.
+    // DO NOTHING becomes a no-op update of the first column rather than INSERT IGNORE,
+    // which would also swallow errors that have nothing to do with duplicates.
+    fn push_on_conflict(&self, query: &mut String, on_conflict: &OnConflict, columns: &[String]) -> Result<(), IdentifierError> {
+        query.push_str(" ON DUPLICATE KEY UPDATE ");
+        match &on_conflict.action {
+            ConflictAction::DoUpdate(updates) if !updates.is_empty() => {
+                for column in updates {
+                    self.push_identifier(query, column)?;
+                    query.push_str(" = VALUES(");
+                    self.push_identifier(query, column)?;
+                    query.push_str("),");
+                }
+                query.pop();
+            },
+            _ => {
+                let column = columns.first().map(String::as_str).unwrap_or_default();
+                self.push_identifier(query, column)?;
+                query.push_str(" = ");
+                self.push_identifier(query, column)?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+
+fn push_direction(query: &mut String, direction: SortDirection) {
+    match direction {
+        SortDirection::Asc => query.push_str(" ASC"),
+        SortDirection::Desc => query.push_str(" DESC"),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format_delete_query_with_dialect, format_insert_returning_query_with_dialect, format_insert_rows_query_with_dialect,
+        format_select_string_with_dialect, format_update_query_with_dialect, format_upsert_query_with_dialect,
+        Condition, OrderBy, SelectOptions, SqlValue,
+    };
+
+    fn columns() -> Vec<String> {
+        Vec::from(["title".to_owned(), "isbn".to_owned()])
+    }
+
+    fn rows() -> Vec<Vec<SqlValue>> {
+        Vec::from([
+            Vec::from([SqlValue::from("Witcher"), SqlValue::from("0")]),
+            Vec::from([SqlValue::from("Sword of Destiny"), SqlValue::from("1")]),
+        ])
+    }
+
+    fn render_all(dialect: &dyn Dialect) -> [String; 9] {
+        let insert = format_insert_rows_query_with_dialect(dialect, "library.book", &columns(), rows()).unwrap().0;
+
+        let updates = Vec::from([("title".to_owned(), SqlValue::from("Witcher"))]);
+        let update = format_update_query_with_dialect(dialect, "book", updates, Condition::eq("isbn", "0")).unwrap().0;
+
+        let condition = Condition::eq("author", "Andrzej Sapkowski").and(Condition::in_list("isbn", Vec::from(["0", "1"])));
+        let options = SelectOptions::new().order_by(OrderBy::asc("title")).limit(10).offset(20);
+        let select = format_select_string_with_dialect(dialect, "book", &columns(), condition, &options).unwrap().0;
+
+        let mut do_update = String::new();
+        let on_conflict = OnConflict::columns(Vec::from(["isbn".to_owned()])).do_update(Vec::from(["title".to_owned()]));
+        dialect.push_on_conflict(&mut do_update, &on_conflict, &columns()).unwrap();
+
+        let mut do_nothing = String::new();
+        dialect.push_on_conflict(&mut do_nothing, &OnConflict::constraint("book_pkey"), &columns()).unwrap();
+
+        let options = SelectOptions::new().order_by(OrderBy::desc("isbn").nulls_last());
+        let ilike = format_select_string_with_dialect(dialect, "book", &columns(), Condition::ilike("title", "the %"), &options).unwrap().0;
+
+        let returning = match format_insert_returning_query_with_dialect(dialect, "book", &columns(), rows().remove(0), &[]) {
+            Ok((query, _)) => query,
+            Err(error) => error.to_string(),
+        };
+
+        let on_conflict = OnConflict::columns(Vec::from(["isbn".to_owned()]));
+        let upsert = format_upsert_query_with_dialect(dialect, "book", &columns(), rows().remove(0), &on_conflict).unwrap().0;
+
+        let delete = format_delete_query_with_dialect(dialect, "book", Condition::eq("isbn", "0")).unwrap().0;
+
+        [insert, update, select, do_update, do_nothing, ilike, returning, upsert, delete]
+    }
+
+    #[test]
+    fn test_postgres_snapshot() {
+        assert_eq!(render_all(&PostgresDialect), [
+            "INSERT INTO \"library\".\"book\" (\"title\",\"isbn\") VALUES ($1,$2),($3,$4)",
+            "UPDATE \"book\" SET \"title\" = $1 WHERE \"isbn\" = $2",
+            "SELECT \"title\",\"isbn\" FROM \"book\" WHERE (\"author\" = $1 AND \"isbn\" IN ($2,$3)) ORDER BY \"title\" ASC LIMIT $4 OFFSET $5",
+            " ON CONFLICT (\"isbn\") DO UPDATE SET \"title\" = EXCLUDED.\"title\"",
+            " ON CONFLICT ON CONSTRAINT \"book_pkey\" DO NOTHING",
+            "SELECT \"title\",\"isbn\" FROM \"book\" WHERE \"title\" ILIKE $1 ORDER BY \"isbn\" DESC NULLS LAST",
+            "INSERT INTO \"book\" (\"title\",\"isbn\") VALUES ($1,$2) RETURNING *",
+            "INSERT INTO \"book\" (\"title\",\"isbn\") VALUES ($1,$2) ON CONFLICT (\"isbn\") DO NOTHING",
+            "DELETE FROM \"book\" WHERE \"isbn\" = $1",
+        ]);
+        assert!(PostgresDialect.supports_returning());
+    }
+
+    #[test]
+    fn test_sqlite_snapshot() {
+        assert_eq!(render_all(&SqliteDialect), [
+            "INSERT INTO \"library\".\"book\" (\"title\",\"isbn\") VALUES (?1,?2),(?3,?4)",
+            "UPDATE \"book\" SET \"title\" = ?1 WHERE \"isbn\" = ?2",
+            "SELECT \"title\",\"isbn\" FROM \"book\" WHERE (\"author\" = ?1 AND \"isbn\" IN (?2,?3)) ORDER BY \"title\" ASC LIMIT ?4 OFFSET ?5",
+            " ON CONFLICT (\"isbn\") DO UPDATE SET \"title\" = EXCLUDED.\"title\"",
+            " ON CONFLICT DO NOTHING",
+            "SELECT \"title\",\"isbn\" FROM \"book\" WHERE \"title\" LIKE ?1 ORDER BY \"isbn\" DESC NULLS LAST",
+            "INSERT INTO \"book\" (\"title\",\"isbn\") VALUES (?1,?2) RETURNING *",
+            "INSERT INTO \"book\" (\"title\",\"isbn\") VALUES (?1,?2) ON CONFLICT (\"isbn\") DO NOTHING",
+            "DELETE FROM \"book\" WHERE \"isbn\" = ?1",
+        ]);
+        assert!(SqliteDialect.supports_returning());
+    }
+
+    #[test]
+    fn test_mysql_snapshot() {
+        assert_eq!(render_all(&MySqlDialect), [
+            "INSERT INTO `library`.`book` (`title`,`isbn`) VALUES (?,?),(?,?)",
+            "UPDATE `book` SET `title` = ? WHERE `isbn` = ?",
+            "SELECT `title`,`isbn` FROM `book` WHERE (`author` = ? AND `isbn` IN (?,?)) ORDER BY `title` ASC LIMIT ? OFFSET ?",
+            " ON DUPLICATE KEY UPDATE `title` = VALUES(`title`)",
+            " ON DUPLICATE KEY UPDATE `title` = `title`",
+            "SELECT `title`,`isbn` FROM `book` WHERE LOWER(`title`) LIKE LOWER(?) ORDER BY `isbn` IS NULL,`isbn` DESC",
+            "RETURNING is not supported by this dialect",
+            "INSERT INTO `book` (`title`,`isbn`) VALUES (?,?) ON DUPLICATE KEY UPDATE `title` = `title`",
+            "DELETE FROM `book` WHERE `isbn` = ?",
+        ]);
+        assert!(!MySqlDialect.supports_returning());
+    }
+
+}
@@ -0,0 +1,250 @@
+use std::{error::Error, fmt, io};
+
+use sqlx::postgres::PgDatabaseError;
+
+use crate::{identifiers::IdentifierError, InvalidCursor, UnfilteredDelete};
+
+
+/// The kind of integrity constraint a statement violated, from the Postgres SQLSTATE
+/// class 23 code or the SQLite extended result code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    Unique,
+    ForeignKey,
+    NotNull,
+    Check,
+    Exclusion,
+    Other,
+}
+
+impl ConstraintKind {
+    // Class 23 is integrity_constraint_violation.
+    fn from_postgres_code(code: &str) -> Option<Self> {
+        let kind = match code {
+            "23505" => ConstraintKind::Unique,
+            "23503" => ConstraintKind::ForeignKey,
+            "23502" => ConstraintKind::NotNull,
+            "23514" => ConstraintKind::Check,
+            "23P01" => ConstraintKind::Exclusion,
+            code if code.starts_with("23") => ConstraintKind::Other,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    // SQLite reports the extended result code, whose low byte is SQLITE_CONSTRAINT (19)
+    // for every constraint failure.
+    fn from_sqlite_code(code: &str) -> Option<Self> {
+        let kind = match code {
+            "2067" | "1555" => ConstraintKind::Unique,
+            "787" => ConstraintKind::ForeignKey,
+            "1299" => ConstraintKind::NotNull,
+            "275" => ConstraintKind::Check,
+            code if code.parse::<i32>().is_ok_and(|code| code & 0xff == 19) => ConstraintKind::Other,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+}
+
+
+/// Everything the helpers can fail with. Errors from sqlx are sorted by what went wrong,
+/// so a duplicate isbn can be told apart from a dropped connection without downcasting.
+#[derive(Debug)]
+pub enum HelperError {
+    /// The database could not be reached or the connection broke.
+    Connection(sqlx::Error),
+    /// The statement violated a constraint. `constraint` is the name Postgres reports,
+    /// which for a unique index is the index name, e.g. `book_isbn_idx`.
+    Constraint {
+        kind: ConstraintKind,
+        constraint: Option<String>,
+        source: sqlx::Error,
+    },
+    /// Any other error reported by the database, such as a syntax error or unknown column.
+    Database(sqlx::Error),
+    /// A returned row could not be turned into `SqlValue`s.
+    Decode(sqlx::Error),
+    /// A row did not fit the struct passed to `select_deserialize`.
+    Deserialize(serde_json::Error),
+    /// A value passed to `insert_struct` or `update_struct` did not serialize to a
+    /// struct-like object, or the rows of a bulk insert had different fields.
+    Serialize(serde_json::Error),
+    Identifier(IdentifierError),
+    /// Reading or writing a file or stream failed, or a delimited file was malformed.
+    Io(io::Error),
+    InvalidCursor(InvalidCursor),
+    UnfilteredDelete(UnfilteredDelete),
+    /// The `Dialect` passed to a builder has no way to write the requested SQL,
+    /// such as RETURNING on MySQL.
+    Unsupported(&'static str),
+    /// `update_strict` matched no rows, or more than `max_rows`.
+    UnexpectedRowCount {
+        table: String,
+        affected: u64,
+        max_rows: Option<u64>,
+    },
+}
+
+impl HelperError {
+    /// The name of the violated constraint, if this is a constraint violation.
+    pub fn constraint(&self) -> Option<&str> {
+        match self {
+            HelperError::Constraint { constraint, .. } => constraint.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HelperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HelperError::Connection(error) => write!(f, "connection error: {error}"),
+            HelperError::Constraint { kind, constraint: Some(name), source } => write!(f, "{kind:?} constraint \"{name}\" violated: {source}"),
+            HelperError::Constraint { kind, constraint: None, source } => write!(f, "{kind:?} constraint violated: {source}"),
+            HelperError::Database(error) => write!(f, "{error}"),
+            HelperError::Decode(error) => write!(f, "decode error: {error}"),
+            HelperError::Deserialize(error) => write!(f, "deserialize error: {error}"),
+            HelperError::Serialize(error) => write!(f, "serialize error: {error}"),
+            HelperError::Identifier(error) => write!(f, "{error}"),
+            HelperError::Io(error) => write!(f, "{error}"),
+            HelperError::InvalidCursor(error) => write!(f, "{error}"),
+            HelperError::UnfilteredDelete(error) => write!(f, "{error}"),
+            HelperError::Unsupported(feature) => write!(f, "{feature} is not supported by this dialect"),
+            HelperError::UnexpectedRowCount { table, affected: 0, .. } => write!(f, "update of \"{table}\" matched no rows"),
+            HelperError::UnexpectedRowCount { table, affected, max_rows } => match max_rows {
+                Some(max_rows) => write!(f, "update of \"{table}\" matched {affected} rows, expected at most {max_rows}"),
+                None => write!(f, "update of \"{table}\" matched {affected} rows"),
+            },
+        }
+    }
+}
+
+impl Error for HelperError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HelperError::Connection(error) => Some(error),
+            HelperError::Constraint { source, .. } => Some(source),
+            HelperError::Database(error) => Some(error),
+            HelperError::Decode(error) => Some(error),
+            HelperError::Deserialize(error) => Some(error),
+            HelperError::Serialize(error) => Some(error),
+            HelperError::Identifier(error) => Some(error),
+            HelperError::Io(error) => Some(error),
+            HelperError::InvalidCursor(error) => Some(error),
+            HelperError::UnfilteredDelete(error) => Some(error),
+            HelperError::Unsupported(_) | HelperError::UnexpectedRowCount { .. } => None,
+        }
+    }
+}
+
+
+impl From<sqlx::Error> for HelperError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::Database(ref database_error) => {
+                let code = database_error.code().unwrap_or_default();
+                let kind = if database_error.try_downcast_ref::<PgDatabaseError>().is_some() {
+                    ConstraintKind::from_postgres_code(&code)
+                } else {
+                    ConstraintKind::from_sqlite_code(&code)
+                };
+                let kind = match kind {
+                    Some(kind) => kind,
+                    None => return HelperError::Database(error),
+                };
+                HelperError::Constraint {
+                    kind,
+                    constraint: database_error.constraint().map(str::to_owned),
+                    source: error,
+                }
+            },
+            sqlx::Error::Configuration(_)
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => HelperError::Connection(error),
+            sqlx::Error::RowNotFound
+            | sqlx::Error::TypeNotFound { .. }
+            | sqlx::Error::ColumnIndexOutOfBounds { .. }
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::Decode(_) => HelperError::Decode(error),
+            _ => HelperError::Database(error),
+        }
+    }
+}
+
+impl From<serde_json::Error> for HelperError {
+    fn from(error: serde_json::Error) -> Self {
+        HelperError::Deserialize(error)
+    }
+}
+
+impl From<io::Error> for HelperError {
+    fn from(error: io::Error) -> Self {
+        HelperError::Io(error)
+    }
+}
+
+impl From<IdentifierError> for HelperError {
+    fn from(error: IdentifierError) -> Self {
+        HelperError::Identifier(error)
+    }
+}
+
+impl From<InvalidCursor> for HelperError {
+    fn from(error: InvalidCursor) -> Self {
+        HelperError::InvalidCursor(error)
+    }
+}
+
+impl From<UnfilteredDelete> for HelperError {
+    fn from(error: UnfilteredDelete) -> Self {
+        HelperError::UnfilteredDelete(error)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlx_error_kinds() {
+        assert!(matches!(HelperError::from(sqlx::Error::PoolTimedOut), HelperError::Connection(_)));
+        assert!(matches!(HelperError::from(sqlx::Error::ColumnNotFound("isbn".to_owned())), HelperError::Decode(_)));
+        assert!(matches!(HelperError::from(sqlx::Error::Protocol("bad message".to_owned())), HelperError::Connection(_)));
+    }
+
+    #[test]
+    fn test_constraint_codes() {
+        assert_eq!(ConstraintKind::from_postgres_code("23505"), Some(ConstraintKind::Unique));
+        assert_eq!(ConstraintKind::from_postgres_code("23000"), Some(ConstraintKind::Other));
+        assert_eq!(ConstraintKind::from_postgres_code("42P01"), None);
+        // 22035 is 0x5613, whose low byte is 19, but it is a Postgres data exception.
+        assert_eq!(ConstraintKind::from_postgres_code("22035"), None);
+        assert_eq!(ConstraintKind::from_postgres_code("2067"), None);
+
+        assert_eq!(ConstraintKind::from_sqlite_code("2067"), Some(ConstraintKind::Unique));
+        assert_eq!(ConstraintKind::from_sqlite_code("19"), Some(ConstraintKind::Other));
+        assert_eq!(ConstraintKind::from_sqlite_code("1"), None);
+        assert_eq!(ConstraintKind::from_sqlite_code("23505"), None);
+    }
+
+    #[test]
+    fn test_wrapped_errors() {
+        let error = HelperError::from(IdentifierError::Empty);
+        assert_eq!(error.to_string(), "identifier is empty");
+        assert!(error.source().is_some());
+        assert_eq!(error.constraint(), None);
+
+        let error = HelperError::from(io::Error::new(io::ErrorKind::InvalidData, "file is empty"));
+        assert!(matches!(error, HelperError::Io(_)));
+    }
+
+}
@@ -0,0 +1,64 @@
+use futures::future::BoxFuture;
+use sqlx::{Column, Executor, Postgres, Row};
+
+use crate::{bind_params, sql_value::decode_rows, HelperError, SqlValue};
+
+
+/// Rows returned by a statement, already decoded into `SqlValue`s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+
+/// Where the single-statement helpers send their SQL. Every sqlx Postgres executor
+/// (`&PgPool`, `&mut PgConnection`, `&mut Transaction`) implements it, and so does
+/// `&MockExecutor`, which lets code built on the helpers be tested without a database.
+pub trait HelperExecutor<'c>: Send {
+    /// Runs a statement and returns the number of rows it affected.
+    fn execute_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<u64, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e;
+
+    fn fetch_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<Rows, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e;
+}
+
+impl<'c, E: Executor<'c, Database = Postgres>> HelperExecutor<'c> for E {
+    fn execute_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<u64, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e,
+    {
+        Box::pin(async move {
+            let result = bind_params(query, params)
+                .execute(self)
+                .await?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    fn fetch_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<Rows, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e,
+    {
+        Box::pin(async move {
+            let rows = bind_params(query, params)
+                .fetch_all(self)
+                .await?;
+
+            let columns = match rows.first() {
+                Some(row) => row.columns().iter().map(|column| column.name().to_owned()).collect(),
+                None => Vec::new(),
+            };
+
+            Ok(Rows { columns, rows: decode_rows(&rows)? })
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use std::{error::Error, fmt};
+
+// Postgres truncates identifiers longer than NAMEDATALEN - 1 bytes.
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdentifierError {
+    Empty,
+    TooLong(String),
+    InvalidCharacter(String, char),
+    TooManyParts(String),
+}
+
+impl fmt::Display for IdentifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentifierError::Empty => write!(f, "identifier is empty"),
+            IdentifierError::TooLong(name) => write!(f, "identifier \"{name}\" is longer than {MAX_IDENTIFIER_LENGTH} bytes"),
+            IdentifierError::InvalidCharacter(name, c) => write!(f, "identifier \"{name}\" contains invalid character {c:?}"),
+            IdentifierError::TooManyParts(name) => write!(f, "identifier \"{name}\" has too many dot-separated parts"),
+        }
+    }
+}
+
+impl Error for IdentifierError {}
+
+
+/// Validates a single column or table name and wraps it in double quotes.
+/// Quoting keeps mixed-case names and reserved words like `order` intact.
+pub fn quote_identifier(name: &str) -> Result<String, IdentifierError> {
+    let mut quoted = String::new();
+    push_identifier(&mut quoted, name)?;
+    Ok(quoted)
+}
+
+/// Like `quote_identifier`, but accepts a schema-qualified name such as `library.book`.
+pub fn quote_qualified_identifier(name: &str) -> Result<String, IdentifierError> {
+    let mut quoted = String::new();
+    push_qualified_identifier(&mut quoted, name)?;
+    Ok(quoted)
+}
+
+
+pub(crate) fn push_identifier(query: &mut String, name: &str) -> Result<(), IdentifierError> {
+    push_identifier_quoted(query, name, '"')
+}
+
+pub(crate) fn push_qualified_identifier(query: &mut String, name: &str) -> Result<(), IdentifierError> {
+    push_qualified_identifier_quoted(query, name, '"')
+}
+
+// `quote` is '"' for standard SQL and '`' for MySQL. Validation is the same for both.
+pub(crate) fn push_identifier_quoted(query: &mut String, name: &str, quote: char) -> Result<(), IdentifierError> {
+    if name.contains('.') {
+        return Err(IdentifierError::TooManyParts(name.to_owned()));
+    }
+    validate_part(name, name)?;
+
+    query.push(quote);
+    query.push_str(name);
+    query.push(quote);
+
+    Ok(())
+}
+
+pub(crate) fn push_qualified_identifier_quoted(query: &mut String, name: &str, quote: char) -> Result<(), IdentifierError> {
+    let parts: Vec<&str> = name.split('.').collect();
+    if parts.len() > 2 {
+        return Err(IdentifierError::TooManyParts(name.to_owned()));
+    }
+
+    for part in &parts {
+        validate_part(name, part)?;
+    }
+
+    for part in parts {
+        query.push(quote);
+        query.push_str(part);
+        query.push(quote);
+        query.push('.');
+    }
+    query.pop();
+
+    Ok(())
+}
+
+
+// Only letters, digits, underscores and dollar signs are accepted, so a validated
+// part never needs escaping inside the quotes. `#[derive(Table)]` checks the same rules.
+fn validate_part(name: &str, part: &str) -> Result<(), IdentifierError> {
+    let mut chars = part.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return Err(IdentifierError::Empty),
+    };
+
+    if part.len() > MAX_IDENTIFIER_LENGTH {
+        return Err(IdentifierError::TooLong(name.to_owned()));
+    }
+
+    if !(first.is_alphabetic() || first == '_') {
+        return Err(IdentifierError::InvalidCharacter(name.to_owned(), first));
+    }
+
+    for c in chars {
+        if !(c.is_alphanumeric() || c == '_' || c == '$') {
+            return Err(IdentifierError::InvalidCharacter(name.to_owned(), c));
+        }
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("title").unwrap(), "\"title\"");
+        assert_eq!(quote_identifier("order").unwrap(), "\"order\"");
+        assert_eq!(quote_identifier("PublishedOn").unwrap(), "\"PublishedOn\"");
+    }
+
+    #[test]
+    fn test_quote_qualified_identifier() {
+        assert_eq!(quote_qualified_identifier("library.book").unwrap(), "\"library\".\"book\"");
+        assert_eq!(quote_qualified_identifier("book").unwrap(), "\"book\"");
+    }
+
+    #[test]
+    fn test_invalid_identifiers() {
+        assert_eq!(quote_identifier(""), Err(IdentifierError::Empty));
+        assert_eq!(quote_identifier("library.book"), Err(IdentifierError::TooManyParts("library.book".to_owned())));
+        assert_eq!(quote_qualified_identifier("a.b.c"), Err(IdentifierError::TooManyParts("a.b.c".to_owned())));
+        assert_eq!(quote_qualified_identifier("library."), Err(IdentifierError::Empty));
+        assert_eq!(quote_identifier("isbn\"; DROP TABLE book; --"), Err(IdentifierError::InvalidCharacter("isbn\"; DROP TABLE book; --".to_owned(), '"')));
+        assert_eq!(quote_identifier("1title"), Err(IdentifierError::InvalidCharacter("1title".to_owned(), '1')));
+        assert!(matches!(quote_identifier(&"a".repeat(64)), Err(IdentifierError::TooLong(_))));
+    }
+
+}
@@ -0,0 +1,188 @@
+use std::{error::Error, fmt};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::{select, sql_value::NAIVE_TIMESTAMP_FORMAT, Condition, HelperError, HelperExecutor, OrderBy, SelectOptions, SortDirection, SqlValue};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidCursor(pub String);
+
+impl fmt::Display for InvalidCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cursor token: {}", self.0)
+    }
+}
+
+impl Error for InvalidCursor {}
+
+
+/// Pages through a table in key order, remembering the last key it returned.
+/// The key column must be unique and non-null, like `book.isbn` behind `book_isbn_idx`,
+/// so each page can start with `WHERE key > last_key` instead of an OFFSET.
+#[derive(Debug, Clone)]
+pub struct KeysetPager {
+    table_name: String,
+    fields: Vec<String>,
+    key: String,
+    condition: Condition,
+    direction: SortDirection,
+    page_size: i64,
+    cursor: Option<SqlValue>,
+    done: bool,
+}
+
+impl KeysetPager {
+    pub fn new(table_name: &str, fields: Vec<String>, key: &str, page_size: i64) -> Self {
+        KeysetPager {
+            table_name: table_name.to_owned(),
+            fields,
+            key: key.to_owned(),
+            condition: Condition::all(Vec::new()),
+            direction: SortDirection::Asc,
+            page_size,
+            cursor: None,
+            done: false,
+        }
+    }
+
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.condition = condition;
+        self
+    }
+
+    pub fn descending(mut self) -> Self {
+        self.direction = SortDirection::Desc;
+        self
+    }
+
+    /// Continues after the row identified by a token from an earlier `cursor_token`.
+    pub fn resume_from(mut self, token: &str) -> Result<Self, InvalidCursor> {
+        self.cursor = Some(decode_cursor(token)?);
+        Ok(self)
+    }
+
+    /// A token for the last key returned so far, or `None` before the first page.
+    pub fn cursor_token(&self) -> Result<Option<String>, InvalidCursor> {
+        match &self.cursor {
+            Some(key) => encode_cursor(key).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the next page, or `None` once the table is exhausted.
+    pub async fn next_page<'c, E: HelperExecutor<'c>>(&mut self, executor: E) -> Result<Option<Vec<Vec<SqlValue>>>, HelperError> {
+        if self.done || self.page_size <= 0 {
+            return Ok(None);
+        }
+
+        // The key is needed to advance the cursor even when the caller didn't ask for it.
+        let mut fields = self.fields.clone();
+        let key_index = match fields.iter().position(|field| field == &self.key) {
+            Some(index) => index,
+            None => {
+                fields.push(self.key.clone());
+                fields.len() - 1
+            },
+        };
+
+        let mut condition = self.condition.clone();
+        if let Some(cursor) = &self.cursor {
+            let after = match self.direction {
+                SortDirection::Asc => Condition::gt(&self.key, cursor.clone()),
+                SortDirection::Desc => Condition::lt(&self.key, cursor.clone()),
+            };
+            condition = condition.and(after);
+        }
+
+        let order = match self.direction {
+            SortDirection::Asc => OrderBy::asc(&self.key),
+            SortDirection::Desc => OrderBy::desc(&self.key),
+        };
+        let options = SelectOptions::new().order_by(order).limit(self.page_size);
+
+        let mut rows = select(&self.table_name, fields, condition, &options, executor).await?;
+
+        if (rows.len() as i64) < self.page_size {
+            self.done = true;
+        }
+
+        let last = match rows.last() {
+            Some(row) => row[key_index].clone(),
+            None => return Ok(None),
+        };
+        self.cursor = Some(last);
+
+        if key_index == self.fields.len() {
+            for row in &mut rows {
+                row.pop();
+            }
+        }
+
+        Ok(Some(rows))
+    }
+}
+
+
+fn encode_cursor(key: &SqlValue) -> Result<String, InvalidCursor> {
+    let token = match key {
+        SqlValue::Int(value) => format!("int:{value}"),
+        SqlValue::Text(value) => format!("text:{value}"),
+        SqlValue::Uuid(value) => format!("uuid:{value}"),
+        SqlValue::Date(value) => format!("date:{value}"),
+        SqlValue::Timestamp(value) => format!("timestamp:{}", value.to_rfc3339()),
+        SqlValue::NaiveTimestamp(value) => format!("naive_timestamp:{}", value.format(NAIVE_TIMESTAMP_FORMAT)),
+        SqlValue::Decimal(value) => format!("decimal:{value}"),
+        other => return Err(InvalidCursor(format!("{other:?} cannot be used as a keyset key"))),
+    };
+
+    Ok(token)
+}
+
+fn decode_cursor(token: &str) -> Result<SqlValue, InvalidCursor> {
+    let invalid = || InvalidCursor(token.to_owned());
+    let (kind, value) = token.split_once(':').ok_or_else(invalid)?;
+
+    let key = match kind {
+        "int" => SqlValue::Int(value.parse().map_err(|_| invalid())?),
+        "text" => SqlValue::Text(value.to_owned()),
+        "uuid" => SqlValue::Uuid(value.parse().map_err(|_| invalid())?),
+        "date" => SqlValue::Date(value.parse::<NaiveDate>().map_err(|_| invalid())?),
+        "timestamp" => SqlValue::Timestamp(DateTime::parse_from_rfc3339(value).map_err(|_| invalid())?.into()),
+        "naive_timestamp" => SqlValue::NaiveTimestamp(NaiveDateTime::parse_from_str(value, NAIVE_TIMESTAMP_FORMAT).map_err(|_| invalid())?),
+        "decimal" => SqlValue::Decimal(value.parse().map_err(|_| invalid())?),
+        _ => return Err(invalid()),
+    };
+
+    Ok(key)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let keys = [
+            SqlValue::Int(42),
+            SqlValue::from("isbn:with:colons"),
+            SqlValue::Date(NaiveDate::from_ymd_opt(2023, 4, 1).unwrap()),
+            SqlValue::NaiveTimestamp(NaiveDate::from_ymd_opt(2023, 4, 1).unwrap().and_hms_micro_opt(12, 0, 0, 250).unwrap()),
+            SqlValue::Decimal(rust_decimal::Decimal::new(1999, 2)),
+        ];
+
+        for key in keys {
+            let token = encode_cursor(&key).unwrap();
+            assert_eq!(decode_cursor(&token).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        assert!(decode_cursor("nonsense").is_err());
+        assert!(decode_cursor("int:abc").is_err());
+        assert!(encode_cursor(&SqlValue::Null).is_err());
+    }
+
+}
@@ -0,0 +1,165 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use futures::future::BoxFuture;
+
+use crate::{HelperError, HelperExecutor, Rows, SqlValue};
+
+
+/// A statement a helper sent to a `MockExecutor` or `Plan`, with its bound parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedStatement {
+    pub query: String,
+    pub params: Vec<SqlValue>,
+}
+
+
+#[derive(Debug)]
+pub enum MockResponse {
+    RowsAffected(u64),
+    Rows(Rows),
+    Error(HelperError),
+}
+
+
+/// Stands in for a database in unit tests. Pass `&mock` wherever a helper takes
+/// an executor; every statement is recorded, and each one is answered with the
+/// next queued response. With nothing queued, writes affect no rows and reads
+/// return no rows.
+///
+/// Every helper that takes a `HelperExecutor` accepts it, including `select_deserialize`
+/// and the `#[derive(Table)]` methods. These still need a real connection:
+///
+/// - `select_as`, which maps rows with sqlx's `FromRow` and so needs real `PgRow`s
+/// - `insert_transaction`, `insert_transaction_with_progress`, `upsert_transaction`,
+///   `update_strict` and `select_with_count`, which open a transaction
+/// - `select_stream`, which streams rows from a sqlx executor as they arrive
+/// - `copy_in`, `copy_in_file`, `export_table`, `export_query` and
+///   `export_table_to_file`, which use the COPY protocol
+/// - everything in the `sqlite` module
+#[derive(Debug, Default)]
+pub struct MockExecutor {
+    statements: Mutex<Vec<RecordedStatement>>,
+    responses: Mutex<VecDeque<MockResponse>>,
+}
+
+impl MockExecutor {
+    pub fn new() -> Self {
+        MockExecutor::default()
+    }
+
+    pub fn push_rows_affected(&self, rows_affected: u64) -> &Self {
+        self.push_response(MockResponse::RowsAffected(rows_affected))
+    }
+
+    pub fn push_rows(&self, columns: &[&str], rows: Vec<Vec<SqlValue>>) -> &Self {
+        let columns = columns.iter().map(|column| column.to_string()).collect();
+        self.push_response(MockResponse::Rows(Rows { columns, rows }))
+    }
+
+    pub fn push_error(&self, error: HelperError) -> &Self {
+        self.push_response(MockResponse::Error(error))
+    }
+
+    pub fn push_response(&self, response: MockResponse) -> &Self {
+        self.responses.lock().unwrap().push_back(response);
+        self
+    }
+
+    /// Every statement received so far, oldest first.
+    pub fn statements(&self) -> Vec<RecordedStatement> {
+        self.statements.lock().unwrap().clone()
+    }
+
+
+    fn respond(&self, query: &str, params: Vec<SqlValue>) -> Option<MockResponse> {
+        self.statements.lock().unwrap().push(RecordedStatement { query: query.to_owned(), params });
+        self.responses.lock().unwrap().pop_front()
+    }
+}
+
+impl<'c> HelperExecutor<'c> for &'c MockExecutor {
+    fn execute_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<u64, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e,
+    {
+        let result = match self.respond(query, params) {
+            Some(MockResponse::RowsAffected(rows_affected)) => Ok(rows_affected),
+            Some(MockResponse::Rows(rows)) => Ok(rows.rows.len() as u64),
+            Some(MockResponse::Error(error)) => Err(error),
+            None => Ok(0),
+        };
+
+        Box::pin(async move { result })
+    }
+
+    fn fetch_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<Rows, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e,
+    {
+        let result = match self.respond(query, params) {
+            Some(MockResponse::Rows(rows)) => Ok(rows),
+            Some(MockResponse::Error(error)) => Err(error),
+            Some(MockResponse::RowsAffected(_)) | None => Ok(Rows::default()),
+        };
+
+        Box::pin(async move { result })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{delete, insert, select, select_deserialize, update, Condition, SelectOptions, UnfilteredDelete};
+
+    #[tokio::test]
+    async fn test_records_statements() {
+        let mock = MockExecutor::new();
+        mock.push_rows_affected(1);
+
+        let updates = Vec::from([("title".to_owned(), SqlValue::from("Witcher"))]);
+        let updated = update("book", updates, Condition::eq("isbn", "0"), &mock).await.unwrap();
+        assert_eq!(updated, 1);
+
+        let indexes = Vec::from(["title".to_owned(), "isbn".to_owned()]);
+        insert("book", &indexes, Vec::from([SqlValue::from("Sword of Destiny"), SqlValue::from("1")]), &mock).await.unwrap();
+
+        assert_eq!(mock.statements(), Vec::from([
+            RecordedStatement {
+                query: "UPDATE \"book\" SET \"title\" = $1 WHERE \"isbn\" = $2".to_owned(),
+                params: Vec::from([SqlValue::from("Witcher"), SqlValue::from("0")]),
+            },
+            RecordedStatement {
+                query: "INSERT INTO \"book\" (\"title\",\"isbn\") VALUES ($1,$2)".to_owned(),
+                params: Vec::from([SqlValue::from("Sword of Destiny"), SqlValue::from("1")]),
+            },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn test_canned_rows_and_errors() {
+        #[derive(serde::Deserialize)]
+        struct Book {
+            title: String,
+        }
+
+        let mock = MockExecutor::new();
+        mock.push_rows(&["title"], Vec::from([Vec::from([SqlValue::from("Witcher")])]))
+            .push_rows(&["title"], Vec::from([Vec::from([SqlValue::from("Blood of Elves")])]))
+            .push_error(HelperError::UnfilteredDelete(UnfilteredDelete("book".to_owned())));
+
+        let fields = Vec::from(["title".to_owned()]);
+        let rows = select("book", fields.clone(), Condition::eq("isbn", "0"), &SelectOptions::default(), &mock).await.unwrap();
+        assert_eq!(rows, Vec::from([Vec::from([SqlValue::from("Witcher")])]));
+
+        let books: Vec<Book> = select_deserialize("book", fields, Condition::eq("isbn", "1"), &SelectOptions::default(), &mock).await.unwrap();
+        assert_eq!(books[0].title, "Blood of Elves");
+
+        let result = delete("book", Condition::eq("isbn", "2"), &mock).await;
+        assert!(matches!(result, Err(HelperError::UnfilteredDelete(_))));
+        assert_eq!(mock.statements().len(), 3);
+    }
+
+}
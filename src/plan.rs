@@ -0,0 +1,207 @@
+use std::{fmt, sync::Mutex};
+
+use futures::future::BoxFuture;
+use serde::Serialize;
+use sqlx::{Acquire, Postgres};
+
+use crate::{
+    format_upsert_counting_query, insert_batches, insert_struct_batches, HelperError, HelperExecutor, IdentifierError, OnConflict, PostgresDialect, RecordedStatement,
+    Rows, SqlValue, MAX_BIND_PARAMS,
+};
+
+
+/// A dry run of the write helpers. Pass `&plan` wherever a helper takes an executor
+/// and the statement it would have run is added to the plan instead; writes report
+/// no rows affected and reads return no rows. `insert_transaction`,
+/// `insert_struct_transaction` and `upsert_transaction` take a connection rather than
+/// an executor, so they are planned with the `Plan` methods of the same name.
+/// `update_strict` is not supported: its check needs the real row count, so plan an
+/// `update` instead.
+///
+/// Print the plan to review it, then `execute` it to run every statement in order
+/// in one transaction.
+///
+/// ```ignore
+/// let plan = Plan::new();
+/// plan.insert_transaction("book", &indexes, rows)?;
+/// update("book", updates, Condition::eq("author", "Andy Sappy"), &plan).await?;
+/// println!("{plan}");
+/// plan.execute(&pool).await?;
+/// ```
+#[derive(Debug, Default)]
+pub struct Plan {
+    statements: Mutex<Vec<RecordedStatement>>,
+}
+
+impl Plan {
+    pub fn new() -> Self {
+        Plan::default()
+    }
+
+    /// Adds the batched INSERT statements `insert_transaction` would run for these rows.
+    pub fn insert_transaction(&self, table_name: &str, indexes: &[String], values: Vec<Vec<SqlValue>>) -> Result<&Self, IdentifierError> {
+        for batch in insert_batches(&PostgresDialect, MAX_BIND_PARAMS, table_name, indexes, values)? {
+            self.push(&batch.query, batch.params);
+        }
+
+        Ok(self)
+    }
+
+    /// Adds the batched INSERT statements `insert_struct_transaction` would run for these structs.
+    pub fn insert_struct_transaction<T: Serialize>(&self, table_name: &str, values: &[T]) -> Result<&Self, HelperError> {
+        for batch in insert_struct_batches(table_name, values)? {
+            self.push(&batch.query, batch.params);
+        }
+
+        Ok(self)
+    }
+
+    /// Adds the upsert `upsert_transaction` would run for each row.
+    pub fn upsert_transaction(&self, table_name: &str, indexes: &[String], values: Vec<Vec<SqlValue>>, on_conflict: &OnConflict) -> Result<&Self, IdentifierError> {
+        for value in values {
+            let (query, params) = format_upsert_counting_query(table_name, indexes, value, on_conflict)?;
+            self.push(&query, params);
+        }
+
+        Ok(self)
+    }
+
+    /// Every planned statement, in the order it will run.
+    pub fn statements(&self) -> Vec<RecordedStatement> {
+        self.statements.lock().unwrap().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.lock().unwrap().is_empty()
+    }
+
+    /// Runs the planned statements in one transaction and returns the total number of rows
+    /// they affected. If any statement fails the transaction is rolled back.
+    pub async fn execute<'c, A: Acquire<'c, Database = Postgres>>(&self, connection: A) -> Result<u64, HelperError> {
+        let mut rows_affected = 0;
+
+        let mut txn = connection.begin().await?;
+        for statement in self.statements() {
+            rows_affected += (&mut txn).execute_statement(&statement.query, statement.params).await?;
+        }
+        txn.commit().await?;
+
+        Ok(rows_affected)
+    }
+
+
+    fn push(&self, query: &str, params: Vec<SqlValue>) {
+        self.statements.lock().unwrap().push(RecordedStatement { query: query.to_owned(), params });
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, statement) in self.statements.lock().unwrap().iter().enumerate() {
+            writeln!(f, "-- statement {}, {} params", i + 1, statement.params.len())?;
+            writeln!(f, "{};", statement.query)?;
+            if !statement.params.is_empty() {
+                writeln!(f, "-- params: {:?}", statement.params)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'c> HelperExecutor<'c> for &'c Plan {
+    fn execute_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<u64, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e,
+    {
+        self.push(query, params);
+        Box::pin(async { Ok(0) })
+    }
+
+    fn fetch_statement<'e, 'q: 'e>(self, query: &'q str, params: Vec<SqlValue>) -> BoxFuture<'e, Result<Rows, HelperError>>
+    where
+        'c: 'e,
+        Self: 'e,
+    {
+        self.push(query, params);
+        Box::pin(async { Ok(Rows::default()) })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+    use crate::{select, test_support::TestDb, update, Condition, OrderBy, SelectOptions};
+
+    #[tokio::test]
+    async fn test_plan_string() -> Result<(), Box<dyn Error>> {
+        let plan = Plan::new();
+
+        let indexes = Vec::from(["title".to_owned(), "author".to_owned(), "isbn".to_owned()]);
+        let rows = (0..30000)
+            .map(|i| Vec::from([SqlValue::from("Batch"), SqlValue::from("Batch Author"), SqlValue::from(format!("batch-{i}"))]))
+            .collect();
+        plan.insert_transaction("book", &indexes, rows)?;
+
+        let updates = Vec::from([("title".to_owned(), SqlValue::from("Witcher"))]);
+        assert_eq!(update("book", updates, Condition::eq("isbn", "0"), &plan).await?, 0);
+
+        let statements = plan.statements();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0].params.len(), 21845 * 3);
+        assert_eq!(statements[1].params.len(), (30000 - 21845) * 3);
+
+        let printed = plan.to_string();
+        assert!(printed.ends_with("-- statement 3, 2 params\nUPDATE \"book\" SET \"title\" = $1 WHERE \"isbn\" = $2;\n-- params: [Text(\"Witcher\"), Text(\"0\")]\n"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_execute() -> Result<(), Box<dyn Error>> {
+        let mut db = TestDb::schema().await?;
+
+        let plan = Plan::new();
+        let indexes = Vec::from(["title".to_owned(), "author".to_owned(), "isbn".to_owned()]);
+        let rows = Vec::from([
+            Vec::from([SqlValue::from("Planned"), SqlValue::from("Plan Author"), SqlValue::from("plan-0")]),
+            Vec::from([SqlValue::from("Planned"), SqlValue::from("Plan Author"), SqlValue::from("plan-1")]),
+        ]);
+        plan.insert_transaction("book", &indexes, rows)?;
+        update("book", Vec::from([("title".to_owned(), SqlValue::from("Executed"))]), Condition::eq("isbn", "plan-1"), &plan).await?;
+
+        let upserts = Vec::from([Vec::from([SqlValue::from("Upserted"), SqlValue::from("Plan Author"), SqlValue::from("plan-0")])]);
+        plan.upsert_transaction("book", &indexes, upserts, &OnConflict::columns(Vec::from(["isbn".to_owned()])).do_update(Vec::from(["title".to_owned()])))?;
+
+        #[derive(serde::Serialize)]
+        struct NewBook {
+            title: &'static str,
+            author: &'static str,
+            isbn: &'static str,
+        }
+        plan.insert_struct_transaction("book", &[NewBook { title: "Struct", author: "Plan Author", isbn: "plan-2" }])?;
+        assert_eq!(plan.statements().len(), 4);
+
+        // Nothing has run yet.
+        let fields = Vec::from(["title".to_owned()]);
+        let output = select("book", fields.clone(), Condition::eq("author", "Plan Author"), &SelectOptions::default(), db.conn()).await?;
+        assert!(output.is_empty());
+
+        assert_eq!(plan.execute(db.conn()).await?, 5);
+
+        let options = SelectOptions::new().order_by(OrderBy::asc("isbn"));
+        let output = select("book", fields, Condition::eq("author", "Plan Author"), &options, db.conn()).await?;
+        assert_eq!(output, Vec::from([
+            Vec::from([SqlValue::from("Upserted")]),
+            Vec::from([SqlValue::from("Executed")]),
+            Vec::from([SqlValue::from("Struct")]),
+        ]));
+
+        Ok(())
+    }
+
+}
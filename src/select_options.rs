@@ -0,0 +1,145 @@
+use crate::{identifiers::IdentifierError, Dialect, SqlValue};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBy {
+    pub column: String,
+    pub direction: SortDirection,
+    pub nulls: Option<NullsOrder>,
+}
+
+impl OrderBy {
+    pub fn asc(column: &str) -> Self {
+        OrderBy { column: column.to_owned(), direction: SortDirection::Asc, nulls: None }
+    }
+
+    pub fn desc(column: &str) -> Self {
+        OrderBy { column: column.to_owned(), direction: SortDirection::Desc, nulls: None }
+    }
+
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls = Some(NullsOrder::First);
+        self
+    }
+
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls = Some(NullsOrder::Last);
+        self
+    }
+}
+
+
+/// Sorting and paging for `select`. The default is unsorted and unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectOptions {
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl SelectOptions {
+    pub fn new() -> Self {
+        SelectOptions::default()
+    }
+
+    pub fn order_by(mut self, order: OrderBy) -> Self {
+        self.order_by.push(order);
+        self
+    }
+
+    // Signed, like the BIGINT Postgres binds LIMIT and OFFSET as. A negative value is
+    // rejected by the database rather than wrapping around.
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+
+    // LIMIT and OFFSET are bound like any other value, so every page of a listing
+    // shares the same statement text.
+    pub(crate) fn push_sql<D: Dialect + ?Sized>(&self, dialect: &D, query: &mut String, params: &mut Vec<SqlValue>) -> Result<(), IdentifierError> {
+        if !self.order_by.is_empty() {
+            query.push_str(" ORDER BY ");
+            for order in &self.order_by {
+                dialect.push_order_by(query, order)?;
+                query.push(',');
+            }
+            query.pop();
+        }
+
+        if let Some(limit) = self.limit {
+            query.push_str(" LIMIT ");
+            params.push(SqlValue::Int(limit));
+            dialect.push_placeholder(query, params.len());
+        }
+
+        if let Some(offset) = self.offset {
+            query.push_str(" OFFSET ");
+            params.push(SqlValue::Int(offset));
+            dialect.push_placeholder(query, params.len());
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PostgresDialect;
+
+    #[test]
+    fn test_select_options_sql() {
+        let options = SelectOptions::new()
+            .order_by(OrderBy::asc("author"))
+            .order_by(OrderBy::desc("title").nulls_last())
+            .limit(10)
+            .offset(20);
+
+        let mut query = String::new();
+        let mut params = Vec::from([SqlValue::from("JRR Tolkien")]);
+        options.push_sql(&PostgresDialect, &mut query, &mut params).unwrap();
+
+        assert_eq!(query, " ORDER BY \"author\" ASC,\"title\" DESC NULLS LAST LIMIT $2 OFFSET $3");
+        assert_eq!(params, Vec::from([SqlValue::from("JRR Tolkien"), SqlValue::Int(10), SqlValue::Int(20)]));
+    }
+
+    #[test]
+    fn test_large_limit_is_not_wrapped() {
+        let mut query = String::new();
+        let mut params = Vec::new();
+        SelectOptions::new().limit(i64::MAX).push_sql(&PostgresDialect, &mut query, &mut params).unwrap();
+
+        assert_eq!(params, Vec::from([SqlValue::Int(i64::MAX)]));
+    }
+
+    #[test]
+    fn test_default_options_are_empty() {
+        let mut query = String::new();
+        let mut params = Vec::new();
+        SelectOptions::default().push_sql(&PostgresDialect, &mut query, &mut params).unwrap();
+
+        assert_eq!(query, "");
+        assert!(params.is_empty());
+    }
+
+}
@@ -0,0 +1,346 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{
+    encode::IsNull,
+    postgres::{types::Oid, PgArgumentBuffer, PgRow, PgTypeInfo},
+    Column, Encode, Postgres, Row, Type, TypeInfo, ValueRef,
+};
+use uuid::Uuid;
+
+
+/// A single value passed to or read back from the database.
+/// Each variant is bound with its native Postgres type, so it can go
+/// straight into INT, BOOL, JSONB, ... columns without casts.
+///
+/// `Timestamp` is a TIMESTAMPTZ and `NaiveTimestamp` a TIMESTAMP without time zone.
+/// They are kept apart because Postgres converts between the two using the session
+/// `TimeZone`, which would shift a TIMESTAMP on its way back in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Json(serde_json::Value),
+    Timestamp(DateTime<Utc>),
+    NaiveTimestamp(NaiveDateTime),
+    Date(NaiveDate),
+    Uuid(Uuid),
+    Decimal(Decimal),
+}
+
+
+// The real type of a SqlValue is only known per value, so the static type_info
+// is a placeholder and `produces` reports the actual type.
+impl Type<Postgres> for SqlValue {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(_ty: &PgTypeInfo) -> bool {
+        true
+    }
+}
+
+impl Encode<'_, Postgres> for SqlValue {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        match self {
+            SqlValue::Null => IsNull::Yes,
+            SqlValue::Bool(value) => <bool as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Int(value) => <i64 as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Float(value) => <f64 as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Text(value) => <String as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Bytes(value) => <Vec<u8> as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Json(value) => <serde_json::Value as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Timestamp(value) => <DateTime<Utc> as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::NaiveTimestamp(value) => <NaiveDateTime as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Date(value) => <NaiveDate as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Uuid(value) => <Uuid as Encode<Postgres>>::encode_by_ref(value, buf),
+            SqlValue::Decimal(value) => <Decimal as Encode<Postgres>>::encode_by_ref(value, buf),
+        }
+    }
+
+    fn produces(&self) -> Option<PgTypeInfo> {
+        let type_info = match self {
+            // OID 0 leaves the parameter untyped so Postgres infers it from the column.
+            SqlValue::Null => PgTypeInfo::with_oid(Oid(0)),
+            SqlValue::Bool(_) => <bool as Type<Postgres>>::type_info(),
+            SqlValue::Int(_) => <i64 as Type<Postgres>>::type_info(),
+            SqlValue::Float(_) => <f64 as Type<Postgres>>::type_info(),
+            SqlValue::Text(_) => <String as Type<Postgres>>::type_info(),
+            SqlValue::Bytes(_) => <Vec<u8> as Type<Postgres>>::type_info(),
+            SqlValue::Json(_) => <serde_json::Value as Type<Postgres>>::type_info(),
+            SqlValue::Timestamp(_) => <DateTime<Utc> as Type<Postgres>>::type_info(),
+            SqlValue::NaiveTimestamp(_) => <NaiveDateTime as Type<Postgres>>::type_info(),
+            SqlValue::Date(_) => <NaiveDate as Type<Postgres>>::type_info(),
+            SqlValue::Uuid(_) => <Uuid as Type<Postgres>>::type_info(),
+            SqlValue::Decimal(_) => <Decimal as Type<Postgres>>::type_info(),
+        };
+
+        Some(type_info)
+    }
+}
+
+
+// Decodes every column of a row according to the column's Postgres type.
+pub(crate) fn decode_row(row: &PgRow) -> Result<Vec<SqlValue>, sqlx::Error> {
+    let mut output = Vec::new();
+    for index in 0..row.len() {
+        output.push(decode_column(row, index)?);
+    }
+
+    Ok(output)
+}
+
+pub(crate) fn decode_rows(rows: &[PgRow]) -> Result<Vec<Vec<SqlValue>>, sqlx::Error> {
+    let mut output = Vec::new();
+    for row in rows {
+        output.push(decode_row(row)?);
+    }
+
+    Ok(output)
+}
+
+pub(crate) fn decode_column(row: &PgRow, index: usize) -> Result<SqlValue, sqlx::Error> {
+    if row.try_get_raw(index)?.is_null() {
+        return Ok(SqlValue::Null);
+    }
+
+    let value = match row.column(index).type_info().name() {
+        "BOOL" => SqlValue::Bool(row.try_get(index)?),
+        "INT2" => SqlValue::Int(row.try_get::<i16, _>(index)? as i64),
+        "INT4" => SqlValue::Int(row.try_get::<i32, _>(index)? as i64),
+        "INT8" => SqlValue::Int(row.try_get(index)?),
+        "FLOAT4" => SqlValue::Float(row.try_get::<f32, _>(index)? as f64),
+        "FLOAT8" => SqlValue::Float(row.try_get(index)?),
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" => SqlValue::Text(row.try_get(index)?),
+        "BYTEA" => SqlValue::Bytes(row.try_get(index)?),
+        "JSON" | "JSONB" => SqlValue::Json(row.try_get(index)?),
+        "TIMESTAMPTZ" => SqlValue::Timestamp(row.try_get(index)?),
+        "TIMESTAMP" => SqlValue::NaiveTimestamp(row.try_get(index)?),
+        "DATE" => SqlValue::Date(row.try_get(index)?),
+        "UUID" => SqlValue::Uuid(row.try_get(index)?),
+        "NUMERIC" => SqlValue::Decimal(row.try_get(index)?),
+        other => return Err(sqlx::Error::ColumnDecode {
+            index: row.column(index).name().to_owned(),
+            source: format!("unsupported column type {other}").into(),
+        }),
+    };
+
+    Ok(value)
+}
+
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::Text(value.to_owned())
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Bool(value)
+    }
+}
+
+impl From<i32> for SqlValue {
+    fn from(value: i32) -> Self {
+        SqlValue::Int(value as i64)
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        SqlValue::Int(value)
+    }
+}
+
+impl From<f64> for SqlValue {
+    fn from(value: f64) -> Self {
+        SqlValue::Float(value)
+    }
+}
+
+impl From<Vec<u8>> for SqlValue {
+    fn from(value: Vec<u8>) -> Self {
+        SqlValue::Bytes(value)
+    }
+}
+
+impl From<serde_json::Value> for SqlValue {
+    fn from(value: serde_json::Value) -> Self {
+        SqlValue::Json(value)
+    }
+}
+
+impl From<DateTime<Utc>> for SqlValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        SqlValue::Timestamp(value)
+    }
+}
+
+impl From<NaiveDateTime> for SqlValue {
+    fn from(value: NaiveDateTime) -> Self {
+        SqlValue::NaiveTimestamp(value)
+    }
+}
+
+impl From<NaiveDate> for SqlValue {
+    fn from(value: NaiveDate) -> Self {
+        SqlValue::Date(value)
+    }
+}
+
+impl From<Uuid> for SqlValue {
+    fn from(value: Uuid) -> Self {
+        SqlValue::Uuid(value)
+    }
+}
+
+impl From<Decimal> for SqlValue {
+    fn from(value: Decimal) -> Self {
+        SqlValue::Decimal(value)
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => SqlValue::Null,
+        }
+    }
+}
+
+/// The reverse of the `From` impls above: takes a decoded value back out of its variant.
+/// A value of any other variant is handed back unchanged as the error.
+pub trait FromSqlValue: Sized {
+    fn from_sql_value(value: SqlValue) -> Result<Self, SqlValue>;
+}
+
+macro_rules! from_sql_value {
+    ($($variant:ident => $type:ty),* $(,)?) => {
+        $(
+            impl FromSqlValue for $type {
+                fn from_sql_value(value: SqlValue) -> Result<Self, SqlValue> {
+                    match value {
+                        SqlValue::$variant(value) => Ok(value),
+                        other => Err(other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_sql_value! {
+    Bool => bool,
+    Int => i64,
+    Float => f64,
+    Text => String,
+    Bytes => Vec<u8>,
+    Json => serde_json::Value,
+    Timestamp => DateTime<Utc>,
+    NaiveTimestamp => NaiveDateTime,
+    Date => NaiveDate,
+    Uuid => Uuid,
+    Decimal => Decimal,
+}
+
+// INT4 and INT2 columns decode to Int, so an i32 field takes any Int that fits.
+impl FromSqlValue for i32 {
+    fn from_sql_value(value: SqlValue) -> Result<Self, SqlValue> {
+        match value {
+            SqlValue::Int(int) => i32::try_from(int).map_err(|_| value),
+            other => Err(other),
+        }
+    }
+}
+
+impl FromSqlValue for SqlValue {
+    fn from_sql_value(value: SqlValue) -> Result<Self, SqlValue> {
+        Ok(value)
+    }
+}
+
+impl<T: FromSqlValue> FromSqlValue for Option<T> {
+    fn from_sql_value(value: SqlValue) -> Result<Self, SqlValue> {
+        match value {
+            SqlValue::Null => Ok(None),
+            value => T::from_sql_value(value).map(Some),
+        }
+    }
+}
+
+// The format chrono's serde implementation uses for NaiveDateTime.
+pub(crate) const NAIVE_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+// Dates, timestamps, uuids and decimals become strings in the formats their own
+// serde implementations read back.
+impl From<SqlValue> for serde_json::Value {
+    fn from(value: SqlValue) -> Self {
+        match value {
+            SqlValue::Null => serde_json::Value::Null,
+            SqlValue::Bool(value) => value.into(),
+            SqlValue::Int(value) => value.into(),
+            SqlValue::Float(value) => value.into(),
+            SqlValue::Text(value) => value.into(),
+            SqlValue::Bytes(value) => value.into(),
+            SqlValue::Json(value) => value,
+            SqlValue::Timestamp(value) => value.to_rfc3339().into(),
+            SqlValue::NaiveTimestamp(value) => value.format(NAIVE_TIMESTAMP_FORMAT).to_string().into(),
+            SqlValue::Date(value) => value.to_string().into(),
+            SqlValue::Uuid(value) => value.to_string().into(),
+            SqlValue::Decimal(value) => value.to_string().into(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_conversions() {
+        assert_eq!(SqlValue::from("Witcher"), SqlValue::Text("Witcher".to_owned()));
+        assert_eq!(SqlValue::from(42), SqlValue::Int(42));
+        assert_eq!(SqlValue::from(Some(true)), SqlValue::Bool(true));
+        assert_eq!(SqlValue::from(None::<i64>), SqlValue::Null);
+
+        assert_eq!(String::from_sql_value(SqlValue::from("Witcher")), Ok("Witcher".to_owned()));
+        assert_eq!(i32::from_sql_value(SqlValue::Int(42)), Ok(42));
+        assert_eq!(i32::from_sql_value(SqlValue::Int(i64::MAX)), Err(SqlValue::Int(i64::MAX)));
+        assert_eq!(Option::<bool>::from_sql_value(SqlValue::Null), Ok(None));
+        assert_eq!(String::from_sql_value(SqlValue::Int(42)), Err(SqlValue::Int(42)));
+    }
+
+    #[test]
+    fn test_into_json() {
+        assert_eq!(serde_json::Value::from(SqlValue::Null), serde_json::Value::Null);
+        assert_eq!(serde_json::Value::from(SqlValue::Int(42)), serde_json::json!(42));
+        assert_eq!(serde_json::Value::from(SqlValue::Bytes(Vec::from([1, 2]))), serde_json::json!([1, 2]));
+        assert_eq!(serde_json::Value::from(SqlValue::Date(NaiveDate::from_ymd_opt(2023, 4, 1).unwrap())), serde_json::json!("2023-04-01"));
+        assert_eq!(serde_json::Value::from(SqlValue::Decimal(Decimal::new(1999, 2))), serde_json::json!("19.99"));
+
+        let noon = NaiveDate::from_ymd_opt(2023, 4, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        assert_eq!(serde_json::Value::from(SqlValue::NaiveTimestamp(noon)), serde_json::json!("2023-04-01T12:00:00"));
+    }
+
+    #[test]
+    fn test_produces_native_types() {
+        let produces = |value: SqlValue| Encode::<Postgres>::produces(&value);
+        assert_eq!(produces(SqlValue::Int(1)), Some(<i64 as Type<Postgres>>::type_info()));
+        assert_eq!(produces(SqlValue::Json(serde_json::json!({"a": 1}))), Some(<serde_json::Value as Type<Postgres>>::type_info()));
+        assert_eq!(produces(SqlValue::Null), Some(PgTypeInfo::with_oid(Oid(0))));
+    }
+
+}
@@ -0,0 +1,238 @@
+//! The insert, update, select and insert_transaction helpers for SQLite, enabled with
+//! the `sqlite` feature. They build their statements with the same `format_*` functions
+//! as the Postgres helpers, using `SqliteDialect`.
+//!
+//! SQLite only stores integers, reals, text and blobs, so values are bound and read
+//! back as those: timestamps, dates, uuids, decimals and JSON go in as text and
+//! come out as `SqlValue::Text`, and booleans come out as `Bool` only from a BOOLEAN column.
+
+use sqlx::{
+    encode::IsNull,
+    query::Query,
+    sqlite::{SqliteArgumentValue, SqliteArguments, SqliteRow, SqliteTypeInfo},
+    Acquire, Column, Encode, Executor, Row, Sqlite, Type, TypeInfo, ValueRef,
+};
+
+use crate::{
+    format_insert_query_with_dialect, format_select_string_with_dialect, format_update_query_with_dialect,
+    insert_batches, Condition, HelperError, SelectOptions, SqlValue, SqliteDialect,
+};
+
+// SQLITE_MAX_VARIABLE_NUMBER for the bundled SQLite.
+const MAX_BIND_PARAMS: usize = 32766;
+
+
+impl Type<Sqlite> for SqlValue {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(_ty: &SqliteTypeInfo) -> bool {
+        true
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for SqlValue {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        let value = match self {
+            SqlValue::Null => return IsNull::Yes,
+            SqlValue::Bool(value) => SqliteArgumentValue::Int(*value as i32),
+            SqlValue::Int(value) => SqliteArgumentValue::Int64(*value),
+            SqlValue::Float(value) => SqliteArgumentValue::Double(*value),
+            SqlValue::Text(value) => SqliteArgumentValue::Text(value.clone().into()),
+            SqlValue::Bytes(value) => SqliteArgumentValue::Blob(value.clone().into()),
+            SqlValue::Json(value) => SqliteArgumentValue::Text(value.to_string().into()),
+            SqlValue::Timestamp(value) => SqliteArgumentValue::Text(value.to_rfc3339().into()),
+            SqlValue::NaiveTimestamp(value) => SqliteArgumentValue::Text(value.format("%F %T%.f").to_string().into()),
+            SqlValue::Date(value) => SqliteArgumentValue::Text(value.to_string().into()),
+            SqlValue::Uuid(value) => SqliteArgumentValue::Text(value.to_string().into()),
+            SqlValue::Decimal(value) => SqliteArgumentValue::Text(value.to_string().into()),
+        };
+        buf.push(value);
+
+        IsNull::No
+    }
+}
+
+
+pub async fn insert<'c, E: Executor<'c, Database = Sqlite>>(table_name: &str, indexes: &[String], values: Vec<SqlValue>, executor: E) -> Result<u64, HelperError> {
+    let (query, params) = format_insert_query_with_dialect(&SqliteDialect, table_name, indexes, values)?;
+
+    let result = bind_params(&query, params)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn update<'c, E: Executor<'c, Database = Sqlite>>(table_name: &str, updates: Vec<(String, SqlValue)>, condition: Condition, executor: E) -> Result<u64, HelperError> {
+    let (query, params) = format_update_query_with_dialect(&SqliteDialect, table_name, updates, condition)?;
+
+    let result = bind_params(&query, params)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn select<'c, E: Executor<'c, Database = Sqlite>>(table_name: &str, fields: Vec<String>, condition: Condition, options: &SelectOptions, executor: E) -> Result<Vec<Vec<SqlValue>>, HelperError> {
+    let (query, params) = format_select_string_with_dialect(&SqliteDialect, table_name, &fields, condition, options)?;
+
+    let rows = bind_params(&query, params)
+        .fetch_all(executor)
+        .await?;
+
+    let mut output = Vec::new();
+    for row in &rows {
+        output.push(decode_row(row)?);
+    }
+
+    Ok(output)
+}
+
+// Same batching as the Postgres version, within SQLite's smaller bind parameter limit.
+pub async fn insert_transaction<'c, A: Acquire<'c, Database = Sqlite>>(table_name: &str, indexes: &[String], values: Vec<Vec<SqlValue>>, connection: A) -> Result<u64, HelperError> {
+    let mut rows_affected = 0;
+
+    let batches = insert_batches(&SqliteDialect, MAX_BIND_PARAMS, table_name, indexes, values)?;
+
+    let mut txn = connection.begin().await?;
+
+    for batch in batches {
+        let result = bind_params(&batch.query, batch.params)
+            .execute(&mut txn)
+            .await?;
+        rows_affected += result.rows_affected();
+    }
+
+    txn.commit().await?;
+
+    Ok(rows_affected)
+}
+
+
+fn bind_params(query: &str, params: Vec<SqlValue>) -> Query<'_, Sqlite, SqliteArguments<'_>> {
+    let mut q = sqlx::query(query);
+    for param in params {
+        q = q.bind(param);
+    }
+
+    q
+}
+
+// Decodes by the storage class of each value rather than the declared column type,
+// since SQLite lets any column hold any of them.
+fn decode_row(row: &SqliteRow) -> Result<Vec<SqlValue>, sqlx::Error> {
+    let mut output = Vec::new();
+    for index in 0..row.len() {
+        let raw = row.try_get_raw(index)?;
+        if raw.is_null() {
+            output.push(SqlValue::Null);
+            continue;
+        }
+
+        let storage = raw.type_info().name().to_owned();
+        let value = match storage.as_str() {
+            "INTEGER" if row.column(index).type_info().name() == "BOOLEAN" => SqlValue::Bool(row.try_get(index)?),
+            "INTEGER" => SqlValue::Int(row.try_get(index)?),
+            "REAL" => SqlValue::Float(row.try_get(index)?),
+            "TEXT" => SqlValue::Text(row.try_get(index)?),
+            "BLOB" => SqlValue::Bytes(row.try_get(index)?),
+            other => return Err(sqlx::Error::ColumnDecode {
+                index: row.column(index).name().to_owned(),
+                source: format!("unsupported storage class {other}").into(),
+            }),
+        };
+        output.push(value);
+    }
+
+    Ok(output)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+
+    use super::*;
+    use crate::{format_insert_returning_query_with_dialect, format_upsert_query_with_dialect, OnConflict, OrderBy};
+
+    // Every connection to sqlite::memory: gets its own database, so keep just one.
+    async fn memory_pool() -> Result<SqlitePool, sqlx::Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await?;
+        sqlx::query("CREATE TABLE book (title TEXT NOT NULL, author TEXT NOT NULL, isbn TEXT NOT NULL, pages INTEGER, in_print BOOLEAN)")
+            .execute(&pool)
+            .await?;
+        sqlx::query("CREATE UNIQUE INDEX book_isbn_idx ON book (isbn)")
+            .execute(&pool)
+            .await?;
+
+        Ok(pool)
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_round_trip() -> Result<(), Box<dyn Error>> {
+        let pool = memory_pool().await?;
+
+        let indexes = Vec::from(["title".to_owned(), "author".to_owned(), "isbn".to_owned(), "pages".to_owned(), "in_print".to_owned()]);
+        let values = Vec::from([SqlValue::from("Witcher"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("0"), SqlValue::from(288), SqlValue::from(true)]);
+        assert_eq!(insert("book", &indexes, values, &pool).await?, 1);
+
+        let updates = Vec::from([("pages".to_owned(), SqlValue::Null)]);
+        assert_eq!(update("book", updates, Condition::eq("isbn", "0"), &pool).await?, 1);
+
+        let output = select("book", indexes, Condition::eq("author", "Andrzej Sapkowski"), &SelectOptions::new().limit(10), &pool).await?;
+        assert_eq!(output, Vec::from([Vec::from([SqlValue::from("Witcher"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("0"), SqlValue::Null, SqlValue::from(true)])]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_insert_transaction() -> Result<(), Box<dyn Error>> {
+        let pool = memory_pool().await?;
+
+        let indexes = Vec::from(["title".to_owned(), "author".to_owned(), "isbn".to_owned()]);
+        let values = (0..20000)
+            .map(|i| Vec::from([SqlValue::from("Batch"), SqlValue::from("Batch Author"), SqlValue::from(format!("batch-{i}"))]))
+            .collect();
+        assert_eq!(insert_transaction("book", &indexes, values, &pool).await?, 20000);
+
+        // A duplicate isbn rolls back the whole transaction.
+        let values = Vec::from([
+            Vec::from([SqlValue::from("New"), SqlValue::from("Someone"), SqlValue::from("new-0")]),
+            Vec::from([SqlValue::from("Dup"), SqlValue::from("Someone"), SqlValue::from("batch-0")]),
+        ]);
+        let error = insert_transaction("book", &indexes, values, &pool).await.unwrap_err();
+        assert!(matches!(error, HelperError::Constraint { .. }));
+
+        let output = select("book", Vec::from(["isbn".to_owned()]), Condition::eq("author", "Someone"), &SelectOptions::default(), &pool).await?;
+        assert!(output.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_dialect_statements() -> Result<(), Box<dyn Error>> {
+        let pool = memory_pool().await?;
+        let indexes = Vec::from(["title".to_owned(), "author".to_owned(), "isbn".to_owned()]);
+
+        let values = Vec::from([SqlValue::from("Witcher"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("0")]);
+        let (query, params) = format_insert_returning_query_with_dialect(&SqliteDialect, "book", &indexes, values, &["isbn".to_owned()])?;
+        let row = bind_params(&query, params).fetch_one(&pool).await?;
+        assert_eq!(decode_row(&row)?, Vec::from([SqlValue::from("0")]));
+
+        let values = Vec::from([SqlValue::from("The Witcher"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("0")]);
+        let on_conflict = OnConflict::columns(Vec::from(["isbn".to_owned()])).do_update(Vec::from(["title".to_owned()]));
+        let (query, params) = format_upsert_query_with_dialect(&SqliteDialect, "book", &indexes, values, &on_conflict)?;
+        bind_params(&query, params).execute(&pool).await?;
+
+        let options = SelectOptions::new().order_by(OrderBy::asc("pages").nulls_last());
+        let output = select("book", Vec::from(["title".to_owned()]), Condition::ilike("title", "the WITCHER"), &options, &pool).await?;
+        assert_eq!(output, Vec::from([Vec::from([SqlValue::from("The Witcher")])]));
+
+        Ok(())
+    }
+
+}
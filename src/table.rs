@@ -0,0 +1,145 @@
+use crate::{
+    format_delete_query, format_insert_query, format_select_string, format_update_query,
+    Condition, FromSqlValue, HelperError, HelperExecutor, SelectOptions, SqlValue,
+};
+
+
+/// A struct stored as one row of a table, keyed by a unique column.
+/// Usually implemented with `#[derive(Table)]`, which also adds `insert`, `update`,
+/// `select_by_key` and `delete` methods that call the functions below.
+pub trait Table: Sized {
+    const NAME: &'static str;
+    const KEY: &'static str;
+    /// Every column, the key included, in the order `values` returns them.
+    const COLUMNS: &'static [&'static str];
+
+    fn values(&self) -> Vec<SqlValue>;
+    fn key(&self) -> SqlValue;
+    /// Builds the struct from one decoded row, finding each field's column by name.
+    fn from_row(columns: &[String], row: Vec<SqlValue>) -> Result<Self, HelperError>;
+}
+
+
+pub async fn insert_record<'c, T: Table, E: HelperExecutor<'c>>(record: &T, executor: E) -> Result<u64, HelperError> {
+    let (query, params) = format_insert_query(T::NAME, &columns::<T>(), record.values())?;
+
+    executor.execute_statement(&query, params).await
+}
+
+// Writes every column except the key to the row with the record's key.
+pub async fn update_record<'c, T: Table, E: HelperExecutor<'c>>(record: &T, executor: E) -> Result<u64, HelperError> {
+    let updates = columns::<T>()
+        .into_iter()
+        .zip(record.values())
+        .filter(|(column, _)| column != T::KEY)
+        .collect();
+    let (query, params) = format_update_query(T::NAME, updates, Condition::eq(T::KEY, record.key()))?;
+
+    executor.execute_statement(&query, params).await
+}
+
+pub async fn select_record<'c, T: Table, E: HelperExecutor<'c>>(key: SqlValue, executor: E) -> Result<Option<T>, HelperError> {
+    let (query, params) = format_select_string(T::NAME, &columns::<T>(), Condition::eq(T::KEY, key), &SelectOptions::default())?;
+
+    let rows = executor.fetch_statement(&query, params).await?;
+
+    match rows.rows.into_iter().next() {
+        Some(row) => Ok(Some(T::from_row(&rows.columns, row)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn delete_record<'c, T: Table, E: HelperExecutor<'c>>(key: SqlValue, executor: E) -> Result<u64, HelperError> {
+    let (query, params) = format_delete_query(T::NAME, Condition::eq(T::KEY, key))?;
+
+    executor.execute_statement(&query, params).await
+}
+
+
+fn columns<T: Table>() -> Vec<String> {
+    T::COLUMNS.iter().map(|column| column.to_string()).collect()
+}
+
+// Moves the value of `column` out of the row, for the `from_row` that `#[derive(Table)]` writes.
+#[doc(hidden)]
+pub fn take_column<T: FromSqlValue>(columns: &[String], row: &mut [SqlValue], column: &str) -> Result<T, HelperError> {
+    let value = match columns.iter().position(|name| name == column).and_then(|index| row.get_mut(index)) {
+        Some(value) => std::mem::replace(value, SqlValue::Null),
+        None => return Err(HelperError::Decode(sqlx::Error::ColumnNotFound(column.to_owned()))),
+    };
+
+    T::from_sql_value(value).map_err(|found| HelperError::Decode(sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: format!("cannot read {found:?} as {}", std::any::type_name::<T>()).into(),
+    }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+    use crate::test_support::TestDb;
+
+    #[derive(Debug, Clone, PartialEq, crate::Table)]
+    #[table(name = "book", key = "isbn")]
+    struct Book {
+        title: String,
+        author: String,
+        isbn: String,
+    }
+
+    #[test]
+    fn test_derived_table() {
+        let book = Book { title: "Witcher".to_owned(), author: "Andrzej Sapkowski".to_owned(), isbn: "0".to_owned() };
+
+        assert_eq!(Book::NAME, "book");
+        assert_eq!(Book::COLUMNS, &["title", "author", "isbn"]);
+        assert_eq!(book.key(), SqlValue::from("0"));
+        assert_eq!(book.values(), Vec::from([SqlValue::from("Witcher"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("0")]));
+    }
+
+    #[tokio::test]
+    async fn test_derived_table_mock() {
+        let mock = crate::MockExecutor::new();
+        mock.push_rows_affected(1);
+
+        let book = Book { title: "Witcher".to_owned(), author: "Andrzej Sapkowski".to_owned(), isbn: "0".to_owned() };
+        assert_eq!(book.update(&mock).await.unwrap(), 1);
+
+        let statements = mock.statements();
+        assert_eq!(statements[0].query, "UPDATE \"book\" SET \"title\" = $1,\"author\" = $2 WHERE \"isbn\" = $3");
+        assert_eq!(statements[0].params, Vec::from([SqlValue::from("Witcher"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("0")]));
+
+        // Columns are matched by name, not position.
+        mock.push_rows(&["isbn", "author", "title"], Vec::from([Vec::from([SqlValue::from("0"), SqlValue::from("Andrzej Sapkowski"), SqlValue::from("Witcher")])]));
+        assert_eq!(Book::select_by_key("0", &mock).await.unwrap(), Some(book));
+        assert_eq!(mock.statements()[1].query, "SELECT \"title\",\"author\",\"isbn\" FROM \"book\" WHERE \"isbn\" = $1");
+
+        assert_eq!(Book::select_by_key("1", &mock).await.unwrap(), None);
+
+        mock.push_rows(&["title", "author", "isbn"], Vec::from([Vec::from([SqlValue::from("Witcher"), SqlValue::Null, SqlValue::from("0")])]));
+        assert!(matches!(Book::select_by_key("0", &mock).await, Err(HelperError::Decode(_))));
+    }
+
+    #[tokio::test]
+    async fn test_derived_table_database() -> Result<(), Box<dyn Error>> {
+        let mut db = TestDb::schema().await?;
+
+        let mut book = Book { title: "The Last Wish".to_owned(), author: "Andrzej Sapkowski".to_owned(), isbn: "derive-0".to_owned() };
+        assert_eq!(book.insert(db.conn()).await?, 1);
+
+        book.title = "The Last Wish (reissue)".to_owned();
+        assert_eq!(book.update(db.conn()).await?, 1);
+
+        let stored = Book::select_by_key("derive-0", db.conn()).await?;
+        assert_eq!(stored, Some(book.clone()));
+
+        assert_eq!(book.delete(db.conn()).await?, 1);
+        assert_eq!(Book::select_by_key("derive-0", db.conn()).await?, None);
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,135 @@
+use crate::{identifiers::IdentifierError, Dialect};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictTarget {
+    Columns(Vec<String>),
+    Constraint(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictAction {
+    DoNothing,
+    DoUpdate(Vec<String>),
+}
+
+
+/// The ON CONFLICT clause of an upsert.
+/// A unique index such as `book_isbn_idx` is not a constraint, so target it by column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnConflict {
+    pub target: ConflictTarget,
+    pub action: ConflictAction,
+}
+
+impl OnConflict {
+    pub fn columns(columns: Vec<String>) -> Self {
+        OnConflict { target: ConflictTarget::Columns(columns), action: ConflictAction::DoNothing }
+    }
+
+    pub fn constraint(name: &str) -> Self {
+        OnConflict { target: ConflictTarget::Constraint(name.to_owned()), action: ConflictAction::DoNothing }
+    }
+
+    pub fn do_nothing(mut self) -> Self {
+        self.action = ConflictAction::DoNothing;
+        self
+    }
+
+    /// Overwrites the given columns with the values from the rejected row.
+    pub fn do_update(mut self, columns: Vec<String>) -> Self {
+        self.action = ConflictAction::DoUpdate(columns);
+        self
+    }
+
+
+    pub(crate) fn push_sql<D: Dialect + ?Sized>(&self, dialect: &D, query: &mut String) -> Result<(), IdentifierError> {
+        query.push_str(" ON CONFLICT");
+        self.push_target(dialect, query)?;
+        self.push_action(dialect, query)
+    }
+
+    pub(crate) fn push_target<D: Dialect + ?Sized>(&self, dialect: &D, query: &mut String) -> Result<(), IdentifierError> {
+        match &self.target {
+            ConflictTarget::Columns(columns) => {
+                query.push_str(" (");
+                for column in columns {
+                    dialect.push_identifier(query, column)?;
+                    query.push(',');
+                }
+                query.pop();
+                query.push(')');
+            },
+            ConflictTarget::Constraint(name) => {
+                query.push_str(" ON CONSTRAINT ");
+                dialect.push_identifier(query, name)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn push_action<D: Dialect + ?Sized>(&self, dialect: &D, query: &mut String) -> Result<(), IdentifierError> {
+        match &self.action {
+            // Updating no columns leaves the stored row as it was, which is DO NOTHING.
+            ConflictAction::DoUpdate(columns) if !columns.is_empty() => {
+                query.push_str(" DO UPDATE SET ");
+                for column in columns {
+                    dialect.push_identifier(query, column)?;
+                    query.push_str(" = EXCLUDED.");
+                    dialect.push_identifier(query, column)?;
+                    query.push(',');
+                }
+                query.pop();
+            },
+            _ => query.push_str(" DO NOTHING"),
+        }
+
+        Ok(())
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpsertCounts {
+    pub inserted: u64,
+    pub updated: u64,
+    pub skipped: u64,
+}
+
+impl std::ops::AddAssign for UpsertCounts {
+    fn add_assign(&mut self, other: UpsertCounts) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.skipped += other.skipped;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PostgresDialect;
+
+    #[test]
+    fn test_on_conflict_sql() {
+        let mut query = String::new();
+        OnConflict::columns(Vec::from(["isbn".to_owned()])).push_sql(&PostgresDialect, &mut query).unwrap();
+        assert_eq!(query, " ON CONFLICT (\"isbn\") DO NOTHING");
+
+        let mut query = String::new();
+        OnConflict::constraint("book_pkey")
+            .do_update(Vec::from(["title".to_owned(), "author".to_owned()]))
+            .push_sql(&PostgresDialect, &mut query)
+            .unwrap();
+        assert_eq!(query, " ON CONFLICT ON CONSTRAINT \"book_pkey\" DO UPDATE SET \"title\" = EXCLUDED.\"title\",\"author\" = EXCLUDED.\"author\"");
+    }
+
+    #[test]
+    fn test_empty_update_is_do_nothing() {
+        let mut query = String::new();
+        OnConflict::columns(Vec::from(["isbn".to_owned()])).do_update(Vec::new()).push_sql(&PostgresDialect, &mut query).unwrap();
+        assert_eq!(query, " ON CONFLICT (\"isbn\") DO NOTHING");
+    }
+
+}